@@ -1,14 +1,16 @@
 use crate::{
     login::LoginComponent,
+    moderation::ModerationComponent,
     problem::{create::CreateComponent, feed::FeedComponent, single_viewer::SingleViewerComponent},
+    vetting::VettingComponent,
 };
-use common::user::User;
+use common::user::{Permission, User};
 use log::*;
 use yew::{
     format::{Json, Nothing},
     prelude::*,
     services::{
-        fetch::{FetchTask, Request, Response},
+        fetch::{Credentials, FetchOptions, FetchTask, Request, Response},
         FetchService,
     },
 };
@@ -20,6 +22,15 @@ pub const API_URL: &'static str = concat!(
     "/api"
 );
 
+/// Sends the session cookie even when the API is on another origin,
+/// like when the webpack dev server on port 8000 calls the API on 8080.
+pub fn fetch_options() -> FetchOptions {
+    FetchOptions {
+        credentials: Some(Credentials::Include),
+        ..FetchOptions::default()
+    }
+}
+
 #[derive(Debug, Switch, Clone)]
 pub enum AppRoute {
     #[to = "/create"]
@@ -28,6 +39,10 @@ pub enum AppRoute {
     Feed,
     #[to = "/login"]
     Login,
+    #[to = "/moderation"]
+    Moderation,
+    #[to = "/vetting"]
+    Vetting,
 }
 
 pub enum AppMsg {
@@ -63,7 +78,9 @@ impl App {
         let request = Request::get(format!("{}/account/", API_URL))
             .body(Nothing)
             .unwrap();
-        self.fetch_service.fetch(request, callback).unwrap()
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
     }
 }
 
@@ -143,6 +160,20 @@ impl Component for App {
                                         <div class="feedroute">
                                             <RouterButton<AppRoute> route=AppRoute::Feed>{"Main Feed"}</RouterButton<AppRoute>>
                                         </div>
+                                        <div class="vettingroute">
+                                            <RouterButton<AppRoute> route=AppRoute::Vetting>{"Vet Answers"}</RouterButton<AppRoute>>
+                                        </div>
+                                        {
+                                            if user.can(Permission::HandleReports) {
+                                                html! {
+                                                    <div class="moderationroute">
+                                                        <RouterButton<AppRoute> route=AppRoute::Moderation>{"Moderation"}</RouterButton<AppRoute>>
+                                                    </div>
+                                                }
+                                            } else {
+                                                html! {}
+                                            }
+                                        }
                                         <div class="loginroute">
                                             <RouterButton<AppRoute> route=AppRoute::Login>{"Sign Out"}</RouterButton<AppRoute>>
                                         </div>
@@ -154,6 +185,8 @@ impl Component for App {
                                             AppRoute::Login => html! { <LoginComponent></LoginComponent> },
                                             AppRoute::Feed => html! { <FeedComponent user=user.clone() feed_endpoint=format!("{}/problems/?", API_URL) /> },
                                             AppRoute::Create => html! { <CreateComponent user_id=user.id /> },
+                                            AppRoute::Moderation => html! { <ModerationComponent user=user.clone() /> },
+                                            AppRoute::Vetting => html! { <VettingComponent /> },
                                         }
                                     })
                                 />
@@ -4,7 +4,9 @@ mod app;
 mod utils;
 
 pub mod login;
+pub mod moderation;
 pub mod problem;
+pub mod vetting;
 
 use wasm_bindgen::prelude::*;
 
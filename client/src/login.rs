@@ -1,4 +1,5 @@
-use crate::app::{AppRoute, API_URL};
+use crate::app::{fetch_options, AppRoute, API_URL};
+use common::pow::Challenge;
 use log::*;
 use serde::{Deserialize, Serialize};
 use yew::{
@@ -15,6 +16,9 @@ use yew_router::{agent::RouteRequest, prelude::*};
 pub struct UserRequest {
     name: String,
     pass: String,
+    /// Only sent when creating an account.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -22,6 +26,7 @@ pub enum LoginMsg {
     ChangeUsername(String),
     ChangePassword(String),
     MakeRequest(bool),
+    ChallengeLoaded(Challenge),
     Failure(String),
     Success,
     NoOp,
@@ -47,11 +52,40 @@ impl LoginComponent {
             .callback(move |event: InputData| LoginMsg::ChangePassword(event.value))
     }
 
+    fn fetch_challenge(&mut self) -> FetchTask {
+        let callback = self.link.callback(
+            move |response: Response<Json<Result<Challenge, anyhow::Error>>>| {
+                let (meta, Json(data)) = response.into_parts();
+                match data {
+                    Ok(challenge) if meta.status.is_success() => {
+                        LoginMsg::ChallengeLoaded(challenge)
+                    }
+                    _ => LoginMsg::Failure(format!("{}", meta.status)),
+                }
+            },
+        );
+        let request = Request::get(format!("{}/account/challenge", API_URL))
+            .body(Nothing)
+            .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
+    }
+
     fn send_request(&mut self, create: bool) -> FetchTask {
         let callback = self.link.callback(move |response: Response<Nothing>| {
             let (meta, _) = response.into_parts();
             if meta.status.is_success() {
                 LoginMsg::Success
+            } else if meta.status.as_u16() == 429 {
+                let wait = meta
+                    .headers
+                    .get("retry-after")
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or("a few");
+                LoginMsg::Failure(format!("Too many attempts, try again in {} seconds", wait))
+            } else if meta.status.as_u16() == 403 && !create {
+                LoginMsg::Failure("This account has been suspended".to_string())
             } else {
                 LoginMsg::Failure(format!("{}", meta.status))
             }
@@ -65,7 +99,9 @@ impl LoginComponent {
             .header("Content-Type", "application/json")
             .body(Json(&self.user_request))
             .unwrap();
-        self.fetch_service.fetch(request, callback).unwrap()
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
     }
 }
 
@@ -82,6 +118,7 @@ impl Component for LoginComponent {
             user_request: UserRequest {
                 name: String::new(),
                 pass: String::new(),
+                nonce: None,
             },
             fetch_service: FetchService::new(),
             ft: None,
@@ -101,7 +138,18 @@ impl Component for LoginComponent {
                 false
             }
             LoginMsg::MakeRequest(create) => {
-                self.ft = Some(self.send_request(create));
+                self.ft = Some(if create {
+                    self.fetch_challenge()
+                } else {
+                    self.user_request.nonce = None;
+                    self.send_request(false)
+                });
+                false
+            }
+            LoginMsg::ChallengeLoaded(challenge) => {
+                // Solving blocks the page, but only for a moment at sensible difficulties
+                self.user_request.nonce = Some(challenge.solve(&self.user_request.name));
+                self.ft = Some(self.send_request(true));
                 false
             }
             LoginMsg::Failure(error) => {
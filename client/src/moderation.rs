@@ -0,0 +1,317 @@
+use crate::{
+    app::{fetch_options, API_URL},
+    problem::wrapper::reason_name,
+};
+use common::{
+    reports::{Report, ReportOutcome, ReportStatus, Resolution},
+    user::User,
+};
+use log::*;
+use std::collections::HashMap;
+use yew::{
+    format::{Json, Nothing},
+    prelude::*,
+    services::{
+        fetch::{FetchTask, Request, Response},
+        FetchService,
+    },
+};
+
+fn outcome_name(outcome: ReportOutcome) -> &'static str {
+    match outcome {
+        ReportOutcome::Dismiss => "Dismiss",
+        ReportOutcome::HideProblem => "Hide problem",
+        ReportOutcome::WarnOwner => "Warn owner",
+        ReportOutcome::SuspendOwner => "Suspend owner",
+    }
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct ModerationProps {
+    pub user: User,
+}
+
+pub enum ModerationMsg {
+    SetFilter(Option<ReportStatus>),
+    Loaded(Vec<Report>),
+    Claim(i32),
+    SetOutcome(i32, ReportOutcome),
+    SetNote(i32, String),
+    Resolve(i32),
+    Updated(Report),
+    Failure(String),
+}
+
+/// The queue of problem reports, where moderators claim and resolve them.
+pub struct ModerationComponent {
+    link: ComponentLink<Self>,
+    fetch_service: FetchService,
+    reports_ft: Option<FetchTask>,
+    action_ft: Option<FetchTask>,
+    props: ModerationProps,
+    /// Only reports with this status are listed, or every report if `None`.
+    filter: Option<ReportStatus>,
+    reports: Vec<Report>,
+    /// The outcome and note chosen for each report, by id.
+    outcomes: HashMap<i32, ReportOutcome>,
+    notes: HashMap<i32, String>,
+    error: Option<String>,
+}
+
+impl ModerationComponent {
+    fn send_reports_request(&mut self) -> FetchTask {
+        let callback = self.link.callback(
+            move |response: Response<Json<Result<Vec<Report>, anyhow::Error>>>| {
+                let (meta, Json(reports)) = response.into_parts();
+                match reports {
+                    Ok(reports) if meta.status.is_success() => ModerationMsg::Loaded(reports),
+                    _ => ModerationMsg::Failure(format!("{}", meta.status)),
+                }
+            },
+        );
+        let query = match self.filter {
+            Some(status) => format!("status={}", status),
+            None => String::new(),
+        };
+        let request = Request::get(format!("{}/reports/?{}", API_URL, query))
+            .body(Nothing)
+            .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
+    }
+
+    fn update_callback(&self) -> Callback<Response<Result<String, anyhow::Error>>> {
+        self.link
+            .callback(move |response: Response<Result<String, anyhow::Error>>| {
+                let (meta, body) = response.into_parts();
+                if meta.status.is_success() {
+                    match body.map(|body| serde_json::from_str::<Report>(&body)) {
+                        Ok(Ok(report)) => ModerationMsg::Updated(report),
+                        _ => ModerationMsg::Failure(
+                            "Unexpected response from the server".to_string(),
+                        ),
+                    }
+                } else {
+                    // Refusals come with a message saying why
+                    ModerationMsg::Failure(body.unwrap_or_else(|_| format!("{}", meta.status)))
+                }
+            })
+    }
+
+    fn send_claim_request(&mut self, id: i32) -> FetchTask {
+        let request = Request::post(format!("{}/reports/{}/claim", API_URL, id))
+            .body(Nothing)
+            .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), self.update_callback())
+            .unwrap()
+    }
+
+    fn send_resolve_request(&mut self, id: i32, resolution: &Resolution) -> FetchTask {
+        let request = Request::post(format!("{}/reports/{}/resolve", API_URL, id))
+            .header("Content-Type", "application/json")
+            .body(Json(resolution))
+            .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), self.update_callback())
+            .unwrap()
+    }
+
+    fn view_filter(&self) -> Html {
+        let filters = [
+            (Some(ReportStatus::Open), "Open"),
+            (Some(ReportStatus::Claimed), "Claimed"),
+            (Some(ReportStatus::Resolved), "Resolved"),
+            (None, "All"),
+        ];
+        html! {
+            <div class="reportfilters">
+                {
+                    for filters.iter().map(|(filter, name)| {
+                        let filter = *filter;
+                        let class = if filter == self.filter { "reportfilter selected" } else { "reportfilter" };
+                        html! {
+                            <div class=class onclick=self.link.callback(move |_| ModerationMsg::SetFilter(filter))>
+                                { name }
+                            </div>
+                        }
+                    })
+                }
+            </div>
+        }
+    }
+
+    fn view_actions(&self, report: &Report) -> Html {
+        let id = report.id;
+        match report.status {
+            ReportStatus::Resolved => html! {
+                <div class="reportresolution">
+                    { report.outcome.map_or("", outcome_name) }
+                    { report.resolution_note.as_ref().map_or(String::new(), |note| format!(": {}", note)) }
+                </div>
+            },
+            ReportStatus::Claimed if report.claimed_by != Some(self.props.user.id) => html! {
+                <div class="reportclaimed">{ "Claimed by another moderator" }</div>
+            },
+            _ => {
+                let selected = self
+                    .outcomes
+                    .get(&id)
+                    .copied()
+                    .unwrap_or(ReportOutcome::Dismiss);
+                let note = self.notes.get(&id).cloned().unwrap_or_default();
+                let select_outcome = self.link.callback(move |data: ChangeData| match data {
+                    ChangeData::Select(select) => match select.value().parse() {
+                        Ok(outcome) => ModerationMsg::SetOutcome(id, outcome),
+                        Err(error) => ModerationMsg::Failure(error),
+                    },
+                    _ => ModerationMsg::Failure("Expected an outcome to be selected".to_string()),
+                });
+                html! {
+                    <div class="reportactions">
+                        {
+                            if report.status == ReportStatus::Open {
+                                html! {
+                                    <div class="claim" onclick=self.link.callback(move |_| ModerationMsg::Claim(id))>
+                                        { "Claim" }
+                                    </div>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
+                        <select onchange=select_outcome>
+                            {
+                                for ReportOutcome::ALL.iter().map(|outcome| html! {
+                                    <option value=outcome.to_string() selected=*outcome == selected>
+                                        { outcome_name(*outcome) }
+                                    </option>
+                                })
+                            }
+                        </select>
+                        <input type="text" class="resolutionnote" placeholder="Note" value=note oninput=self.link.callback(move |event: InputData| ModerationMsg::SetNote(id, event.value)) />
+                        <div class="resolve" onclick=self.link.callback(move |_| ModerationMsg::Resolve(id))>
+                            { "Resolve" }
+                        </div>
+                    </div>
+                }
+            }
+        }
+    }
+
+    fn view_report(&self, report: &Report) -> Html {
+        html! {
+            <div class="report">
+                <div class="reportheader">
+                    <span class="reportreason">{ reason_name(report.reason) }</span>
+                    <span class="reportproblem">{ format!("Problem #{}", report.problem_id) }</span>
+                    <span class="reportdate">{ report.created_at.format("%Y-%m-%d %H:%M").to_string() }</span>
+                </div>
+                <div class="reportcomment">{ &report.comment }</div>
+                { self.view_actions(report) }
+            </div>
+        }
+    }
+}
+
+impl Component for ModerationComponent {
+    type Message = ModerationMsg;
+    type Properties = ModerationProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let mut component = Self {
+            link,
+            fetch_service: FetchService::new(),
+            reports_ft: None,
+            action_ft: None,
+            props,
+            filter: Some(ReportStatus::Open),
+            reports: vec![],
+            outcomes: HashMap::new(),
+            notes: HashMap::new(),
+            error: None,
+        };
+        component.reports_ft = Some(component.send_reports_request());
+        component
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            ModerationMsg::SetFilter(filter) => {
+                self.filter = filter;
+                self.reports_ft = Some(self.send_reports_request());
+                true
+            }
+            ModerationMsg::Loaded(reports) => {
+                self.reports = reports;
+                self.error = None;
+                true
+            }
+            ModerationMsg::Claim(id) => {
+                self.action_ft = Some(self.send_claim_request(id));
+                false
+            }
+            ModerationMsg::SetOutcome(id, outcome) => {
+                self.outcomes.insert(id, outcome);
+                false
+            }
+            ModerationMsg::SetNote(id, note) => {
+                self.notes.insert(id, note);
+                false
+            }
+            ModerationMsg::Resolve(id) => {
+                let resolution = Resolution {
+                    outcome: self
+                        .outcomes
+                        .get(&id)
+                        .copied()
+                        .unwrap_or(ReportOutcome::Dismiss),
+                    note: self.notes.get(&id).cloned().unwrap_or_default(),
+                };
+                if let Err(error) = resolution.validate() {
+                    self.error = Some(error);
+                    return true;
+                }
+                self.action_ft = Some(self.send_resolve_request(id, &resolution));
+                false
+            }
+            ModerationMsg::Updated(report) => {
+                if let Some(old) = self.reports.iter_mut().find(|old| old.id == report.id) {
+                    *old = report;
+                }
+                self.error = None;
+                true
+            }
+            ModerationMsg::Failure(error) => {
+                info!("Error when moderating reports: {}", error);
+                self.error = Some(error);
+                true
+            }
+        }
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div class="moderationwrapper">
+                <div class="moderationqueue">
+                    { self.view_filter() }
+                    {
+                        if let Some(error) = &self.error {
+                            html! { <div class="errorbox">{ error }</div> }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    {
+                        if self.reports.is_empty() {
+                            html! { <div class="noreports">{ "Nothing to see here" }</div> }
+                        } else {
+                            html! { for self.reports.iter().map(|report| self.view_report(report)) }
+                        }
+                    }
+                </div>
+            </div>
+        }
+    }
+}
@@ -1,5 +1,9 @@
-use crate::app::{AppRoute, API_URL};
-use common::problems::{NewProblem, ProblemContent, ProblemType, Topic};
+use crate::app::{fetch_options, AppRoute, API_URL};
+use common::{
+    grid::{CageOp, GridConstraint, GridPuzzle, PuzzleKind},
+    problems::{NewProblem, Part, ProblemContent, ProblemType, Topic},
+    random::SeededRng,
+};
 use log::*;
 use serde::{Deserialize, Serialize};
 use yew::{
@@ -14,7 +18,7 @@ use yew_router::{agent::RouteRequest, prelude::*};
 
 #[derive(Debug, Clone)]
 struct ProblemBuilder {
-    topic: Option<Topic>,
+    topic_id: Option<i32>,
     tags: Vec<String>,
     prompt: String,
     content: ProblemContentBuilder,
@@ -23,7 +27,7 @@ struct ProblemBuilder {
 impl Default for ProblemBuilder {
     fn default() -> Self {
         Self {
-            topic: Option::default(),
+            topic_id: Option::default(),
             tags: Vec::default(),
             prompt: String::default(),
             content: ProblemContentBuilder::default_from_type(&ProblemType::MultipleChoice),
@@ -37,6 +41,25 @@ pub enum ProblemContentBuilder {
         options: Vec<String>,
         solution: Option<usize>,
     },
+    Ordering {
+        items: Vec<String>,
+        partial_credit: bool,
+    },
+    Matching {
+        pairs: Vec<(String, String)>,
+    },
+    MultiPart {
+        parts: Vec<Part>,
+        part_prompt: String,
+        part_weight: f32,
+        /// The part currently being written, added to `parts` once it's done.
+        current: Box<ProblemContentBuilder>,
+    },
+    Grid {
+        puzzle: GridPuzzle,
+        /// How many solutions the last uniqueness check found (up to 2).
+        solutions: Option<usize>,
+    },
 }
 
 impl ProblemContentBuilder {
@@ -45,6 +68,105 @@ impl ProblemContentBuilder {
             ProblemContentBuilder::MultipleChoice { options, solution } => {
                 ProblemType::MultipleChoice
             }
+            ProblemContentBuilder::Ordering { .. } => ProblemType::Ordering,
+            ProblemContentBuilder::Matching { .. } => ProblemType::Matching,
+            ProblemContentBuilder::MultiPart { .. } => ProblemType::MultiPart,
+            ProblemContentBuilder::Grid { .. } => ProblemType::Grid,
+        }
+    }
+
+    /// An empty `size` by `size` grid puzzle of the given kind.
+    fn grid(kind: PuzzleKind, size: usize) -> Self {
+        ProblemContentBuilder::Grid {
+            puzzle: GridPuzzle {
+                kind,
+                rows: size,
+                cols: size,
+                givens: vec![],
+                constraints: vec![],
+            },
+            solutions: None,
+        }
+    }
+
+    /// The builder that choice edits apply to: the current
+    /// part of a multi-part problem, or else the problem itself.
+    fn active_mut(&mut self) -> &mut ProblemContentBuilder {
+        match self {
+            ProblemContentBuilder::MultiPart { current, .. } => current,
+            builder => builder,
+        }
+    }
+
+    /// Validates the builder and turns it into content, using `seed_text`
+    /// to scramble anything whose stored order would give the answer away.
+    fn build(&self, seed_text: &str) -> Result<ProblemContent, String> {
+        match self {
+            ProblemContentBuilder::MultipleChoice { options, solution } => {
+                if options.len() <= 1 {
+                    return Err("Please provide at least 2 answer options".into());
+                }
+                if let Some(solution) = *solution {
+                    Ok(ProblemContent::MultipleChoice {
+                        options: options.clone(),
+                        solution,
+                    })
+                } else {
+                    Err("Please select a solution by clicking on one of the response options".into())
+                }
+            }
+            ProblemContentBuilder::Ordering {
+                items,
+                partial_credit,
+            } => {
+                if items.len() <= 1 {
+                    return Err("Please provide at least 2 items".into());
+                }
+                // Scramble the items so the stored order doesn't give the answer away
+                let mut order: Vec<usize> = (0..items.len()).collect();
+                SeededRng::from_bytes(seed_text.as_bytes()).shuffle(&mut order);
+                let mut solution_order = vec![0; items.len()];
+                for (shown_idx, item_idx) in order.iter().enumerate() {
+                    solution_order[*item_idx] = shown_idx;
+                }
+                Ok(ProblemContent::Ordering {
+                    items: order.iter().map(|idx| items[*idx].clone()).collect(),
+                    solution_order,
+                    partial_credit: *partial_credit,
+                })
+            }
+            ProblemContentBuilder::Matching { pairs } => {
+                if pairs.len() <= 1 {
+                    return Err("Please provide at least 2 pairs".into());
+                }
+                // Scramble the right-hand side so pairs aren't stored side by side
+                let mut order: Vec<usize> = (0..pairs.len()).collect();
+                SeededRng::from_bytes(seed_text.as_bytes()).shuffle(&mut order);
+                Ok(ProblemContent::Matching {
+                    left: pairs.iter().map(|(left, _)| left.clone()).collect(),
+                    right: order.iter().map(|idx| pairs[*idx].1.clone()).collect(),
+                    pairs: order
+                        .iter()
+                        .enumerate()
+                        .map(|(right_idx, left_idx)| (*left_idx, right_idx))
+                        .collect(),
+                })
+            }
+            ProblemContentBuilder::MultiPart { parts, .. } => {
+                if parts.is_empty() {
+                    return Err("Please add at least one part".into());
+                }
+                Ok(ProblemContent::MultiPart {
+                    parts: parts.clone(),
+                })
+            }
+            ProblemContentBuilder::Grid { puzzle, .. } => {
+                puzzle.validate()?;
+                if puzzle.count_solutions(1) == 0 {
+                    return Err("This puzzle has no solution".into());
+                }
+                Ok(ProblemContent::Grid(puzzle.clone()))
+            }
         }
     }
 }
@@ -56,15 +178,75 @@ impl ProblemContentBuilder {
                 options: vec![],
                 solution: None,
             },
+            ProblemType::Ordering => ProblemContentBuilder::Ordering {
+                items: vec![],
+                partial_credit: false,
+            },
+            ProblemType::Matching => ProblemContentBuilder::Matching { pairs: vec![] },
+            ProblemType::Grid => {
+                ProblemContentBuilder::grid(PuzzleKind::Sudoku { box_rows: 3, box_cols: 3 }, 9)
+            }
+            ProblemType::MultiPart => ProblemContentBuilder::MultiPart {
+                parts: vec![],
+                part_prompt: String::new(),
+                part_weight: 1.0,
+                current: Box::new(ProblemContentBuilder::default_from_type(
+                    &ProblemType::MultipleChoice,
+                )),
+            },
             _ => unimplemented!(),
         }
     }
 }
 
+/// The largest nonogram or KenKen the studio will build, to
+/// keep the uniqueness check quick enough to run in the browser.
+const MAX_GRID_SIZE: usize = 15;
+
+/// Parses a cage written as its cells, an operator and a target, e.g.
+/// `1,1 1,2 + 3`. Rows and columns count from 1, and a
+/// single-cell cage may leave out the operator.
+fn parse_cage(text: &str) -> Result<GridConstraint, String> {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+    let (target, rest) = tokens
+        .split_last()
+        .ok_or_else(|| "Please describe the cage, e.g. 1,1 1,2 + 3".to_string())?;
+    let target = target
+        .parse::<u32>()
+        .map_err(|_| format!("\"{}\" is not a target number", target))?;
+    let (op, cells) = match rest.split_last() {
+        Some((op, cells)) if !op.contains(',') => {
+            let op = match *op {
+                "+" | "=" => CageOp::Add,
+                "-" | "−" => CageOp::Subtract,
+                "*" | "x" | "×" => CageOp::Multiply,
+                "/" | "÷" => CageOp::Divide,
+                _ => return Err(format!("\"{}\" is not one of + - * /", op)),
+            };
+            (op, cells)
+        }
+        _ => (CageOp::Add, rest),
+    };
+    let cells = cells
+        .iter()
+        .map(|cell| {
+            let coords: Vec<usize> = cell.split(',').filter_map(|coord| coord.parse().ok()).collect();
+            match coords[..] {
+                [row, col] if row > 0 && col > 0 => Ok((row - 1, col - 1)),
+                _ => Err(format!("\"{}\" is not a cell like 1,2", cell)),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if cells.is_empty() {
+        return Err("A cage needs at least one cell".to_string());
+    }
+    Ok(GridConstraint::Cage { cells, op, target })
+}
+
 pub enum CreateMsg {
     NoOp,
     // All problems
-    SetTopic(Topic),
+    SetTopic(i32),
     SetContent(ProblemContentBuilder),
     UpdateTagText(String),
     RemoveTag(usize),
@@ -76,7 +258,26 @@ pub enum CreateMsg {
     UpdateChoice(String),
     AddChoice,
     RemoveChoice(usize),
+    // Ordering
+    MoveChoice(usize, bool),
+    TogglePartialCredit,
+    // Matching
+    UpdateMatch(String),
+    // Grid
+    SetGridKind(PuzzleKind, usize),
+    UpdateGridSize(String),
+    UpdateGridCell(usize, usize, String),
+    UpdateGridClue(bool, usize, String),
+    CheckUniqueness,
+    // Multi-part
+    SetPartContent(ProblemContentBuilder),
+    UpdatePartPrompt(String),
+    UpdatePartWeight(String),
+    AddPart,
+    RemovePart(usize),
     // Requests stuff
+    TopicsLoaded(Vec<Topic>),
+    TopicsFailed(String),
     CreationSuccess(i32),
     CreationFailure(String),
 }
@@ -92,16 +293,18 @@ pub struct CreateComponent {
     router: Box<dyn Bridge<RouteAgent>>,
     fetch_service: FetchService,
     ft: Option<FetchTask>,
+    topics_ft: Option<FetchTask>,
+    topics: Vec<Topic>,
     builder: ProblemBuilder,
     new_tag_text: String,
     content_input_buffer: String,
+    match_input_buffer: String,
     error_message: String,
 }
 
 impl CreateComponent {
-    fn set_topic(&self, topic: Topic) -> Callback<MouseEvent> {
-        self.link
-            .callback(move |_| CreateMsg::SetTopic(topic.clone()))
+    fn set_topic(&self, topic_id: i32) -> Callback<MouseEvent> {
+        self.link.callback(move |_| CreateMsg::SetTopic(topic_id))
     }
 
     fn update_tag_text(&self) -> Callback<InputData> {
@@ -144,10 +347,123 @@ impl CreateComponent {
         self.link.callback(move |_| CreateMsg::RemoveChoice(idx))
     }
 
+    fn move_choice(&self, idx: usize, up: bool) -> Callback<MouseEvent> {
+        self.link.callback(move |_| CreateMsg::MoveChoice(idx, up))
+    }
+
+    fn update_match(&self) -> Callback<InputData> {
+        self.link
+            .callback(move |data: InputData| CreateMsg::UpdateMatch(data.value))
+    }
+
+    fn toggle_partial_credit(&self) -> Callback<MouseEvent> {
+        self.link.callback(|_| CreateMsg::TogglePartialCredit)
+    }
+
+    fn set_grid_kind(&self, kind: PuzzleKind, size: usize) -> Callback<MouseEvent> {
+        self.link
+            .callback(move |_| CreateMsg::SetGridKind(kind.clone(), size))
+    }
+
+    fn update_grid_size(&self) -> Callback<InputData> {
+        self.link
+            .callback(move |data: InputData| CreateMsg::UpdateGridSize(data.value))
+    }
+
+    fn update_grid_cell(&self, row: usize, col: usize) -> Callback<InputData> {
+        self.link
+            .callback(move |data: InputData| CreateMsg::UpdateGridCell(row, col, data.value))
+    }
+
+    fn update_grid_clue(&self, is_row: bool, idx: usize) -> Callback<InputData> {
+        self.link
+            .callback(move |data: InputData| CreateMsg::UpdateGridClue(is_row, idx, data.value))
+    }
+
+    fn check_uniqueness(&self) -> Callback<MouseEvent> {
+        self.link.callback(|_| CreateMsg::CheckUniqueness)
+    }
+
+    fn set_part_content(&self, content: ProblemContentBuilder) -> Callback<MouseEvent> {
+        self.link
+            .callback(move |_| CreateMsg::SetPartContent(content.clone()))
+    }
+
+    fn update_part_prompt(&self) -> Callback<InputData> {
+        self.link
+            .callback(move |data: InputData| CreateMsg::UpdatePartPrompt(data.value))
+    }
+
+    fn update_part_weight(&self) -> Callback<InputData> {
+        self.link
+            .callback(move |data: InputData| CreateMsg::UpdatePartWeight(data.value))
+    }
+
+    fn add_part(&self) -> Callback<MouseEvent> {
+        self.link.callback(|_| CreateMsg::AddPart)
+    }
+
+    fn remove_part(&self, idx: usize) -> Callback<MouseEvent> {
+        self.link.callback(move |_| CreateMsg::RemovePart(idx))
+    }
+
     fn finish_problem(&self) -> Callback<MouseEvent> {
         self.link.callback(|_| CreateMsg::Finish)
     }
 
+    fn send_topics_request(&mut self) -> FetchTask {
+        let callback = self.link.callback(
+            move |response: Response<Json<Result<Vec<Topic>, anyhow::Error>>>| {
+                let (meta, Json(topics)) = response.into_parts();
+                if meta.status.is_success() {
+                    match topics {
+                        Ok(topics) => CreateMsg::TopicsLoaded(topics),
+                        Err(error) => CreateMsg::TopicsFailed(format!("{}", error)),
+                    }
+                } else {
+                    CreateMsg::TopicsFailed(format!("{}", meta.status))
+                }
+            },
+        );
+        let request = Request::get(format!("{}/topics/", API_URL))
+            .body(Nothing)
+            .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
+    }
+
+    /// Renders the topics under `parent` (the top level if `None`) and their subtopics.
+    fn view_topic_tree(&self, parent: Option<i32>) -> Html {
+        let children = Topic::children(&self.topics, parent);
+        if children.is_empty() {
+            return html! {};
+        }
+        html! {
+            <ul class={ if parent.is_none() { "topictree" } else { "subtopics" } }>
+                {
+                    for children.into_iter().map(|topic| html! {
+                        <li>
+                            <div
+                                class={
+                                    if self.builder.topic_id == Some(topic.id) {
+                                        "topicnode selected"
+                                    } else {
+                                        "topicnode"
+                                    }
+                                }
+                                title=&topic.description
+                                onclick=&self.set_topic(topic.id)>
+                                { topic.display_name() }
+                            </div>
+                            { self.view_topic_tree(Some(topic.id)) }
+                        </li>
+                    })
+                }
+            </ul>
+        }
+    }
+
     fn send_creation_request(&mut self, new_problem: NewProblem) -> FetchTask {
         let callback = self.link.callback(
             move |response: Response<Json<Result<i32, anyhow::Error>>>| {
@@ -166,7 +482,347 @@ impl CreateComponent {
             .header("Content-Type", "application/json")
             .body(Json(&new_problem))
             .unwrap();
-        self.fetch_service.fetch(request, callback).unwrap()
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
+    }
+
+    fn view_type_options(&self, selected: &ProblemType, for_part: bool) -> Html {
+        let mut problem_types = vec![ProblemType::MultipleChoice, ProblemType::FreeResponse, ProblemType::Checklist, ProblemType::Ordering, ProblemType::Matching, ProblemType::Grid];
+        // Parts can't themselves be split into parts
+        if !for_part {
+            problem_types.push(ProblemType::MultiPart);
+        }
+        html! {
+            <div class="problemtypeoptions">
+                {
+                    for problem_types.iter().enumerate().map(|(i, p_type)| {
+                        html! {
+                            <div
+                                class={
+                                    let mut classes = "type".to_string();
+                                    if selected == p_type {
+                                        classes.push_str(" selected");
+                                    }
+                                    if i == 0 {
+                                        classes.push_str(" left");
+                                    } else if i + 1 == problem_types.len() {
+                                        classes.push_str(" right");
+                                    } else {
+                                        classes.push_str(" center");
+                                    }
+                                    classes
+                                }
+                                onclick={
+                                    match p_type {
+                                        ProblemType::MultipleChoice | ProblemType::Ordering | ProblemType::Matching | ProblemType::Grid => {
+                                            let content = ProblemContentBuilder::default_from_type(&p_type);
+                                            if for_part {
+                                                self.set_part_content(content)
+                                            } else {
+                                                self.set_content(content)
+                                            }
+                                        }
+                                        ProblemType::MultiPart if !for_part => {
+                                            self.set_content(ProblemContentBuilder::default_from_type(&p_type))
+                                        }
+                                        _ => self.link.callback(|_| CreateMsg::NoOp),
+                                    }
+                                }
+                                >
+                                {
+                                    serde_json::to_string(&p_type).unwrap().replace(r#"""#, "")
+                                }
+                            </div>
+                        }
+                    })
+                }
+            </div>
+        }
+    }
+
+    fn view_content_builder(&self, content: &ProblemContentBuilder, step: &str) -> Html {
+        match content {
+            ProblemContentBuilder::MultipleChoice { options, solution } => html!{
+                <div class="multiplechoice">
+                    <div class="prompt">
+                        { format!("{}: Add choices and click the correct one", step) }
+                    </div>
+                    <div class="addchoice">
+                        <input type="text" oninput=&self.update_choice() />
+                        <button class="add" onclick=&self.add_choice()>{ "Add choice" }</button>
+                    </div>
+                    <div class="multiplechoicedisplay">
+                        {
+                            for options.iter().enumerate().map(|(i, option)| html! {
+                                <div class="optionwrapper">
+                                    <span class="optionmarker" onclick=&self.remove_choice(i)>{ "X" }</span>
+                                    <div onclick=&self.add_solution(i) class={
+                                        if Some(i) == *solution {
+                                            "option selected"
+                                        } else {
+                                            "option"
+                                        }
+                                    }>{option}</div>
+                                </div>
+                            })
+                        }
+                    </div>
+                </div>
+            },
+            ProblemContentBuilder::Ordering { items, partial_credit } => html!{
+                <div class="ordering">
+                    <div class="prompt">
+                        { format!("{}: Add items and arrange them in the correct order", step) }
+                    </div>
+                    <div class="addchoice">
+                        <input type="text" oninput=&self.update_choice() />
+                        <button class="add" onclick=&self.add_choice()>{ "Add item" }</button>
+                    </div>
+                    <div class="orderingdisplay">
+                        {
+                            for items.iter().enumerate().map(|(i, item)| html! {
+                                <div class="optionwrapper">
+                                    <span class="optionmarker" onclick=&self.remove_choice(i)>{ "X" }</span>
+                                    <span class="optionmarker" onclick=&self.move_choice(i, true)>{ "↑" }</span>
+                                    <span class="optionmarker" onclick=&self.move_choice(i, false)>{ "↓" }</span>
+                                    <div class="option">{item}</div>
+                                </div>
+                            })
+                        }
+                    </div>
+                    <label class="partialcredit">
+                        <input type="checkbox" checked=*partial_credit onclick=&self.toggle_partial_credit() />
+                        { "Give partial credit for nearly correct orders" }
+                    </label>
+                </div>
+            },
+            ProblemContentBuilder::Matching { pairs } => html!{
+                <div class="matching">
+                    <div class="prompt">
+                        { format!("{}: Add each term and the answer it matches", step) }
+                    </div>
+                    <div class="addchoice">
+                        <input type="text" placeholder="Term" oninput=&self.update_choice() />
+                        <input type="text" placeholder="Match" oninput=&self.update_match() />
+                        <button class="add" onclick=&self.add_choice()>{ "Add pair" }</button>
+                    </div>
+                    <div class="matchingdisplay">
+                        {
+                            for pairs.iter().enumerate().map(|(i, (left, right))| html! {
+                                <div class="optionwrapper">
+                                    <span class="optionmarker" onclick=&self.remove_choice(i)>{ "X" }</span>
+                                    <div class="option">{ format!("{} ⟷ {}", left, right) }</div>
+                                </div>
+                            })
+                        }
+                    </div>
+                </div>
+            },
+            ProblemContentBuilder::Grid { puzzle, solutions } => {
+                let presets = vec![
+                    ("4×4 sudoku", PuzzleKind::Sudoku { box_rows: 2, box_cols: 2 }, 4),
+                    ("6×6 sudoku", PuzzleKind::Sudoku { box_rows: 2, box_cols: 3 }, 6),
+                    ("9×9 sudoku", PuzzleKind::Sudoku { box_rows: 3, box_cols: 3 }, 9),
+                    ("Nonogram", PuzzleKind::Nonogram, 5),
+                    ("KenKen", PuzzleKind::KenKen, 4),
+                ];
+                let nonogram = puzzle.kind == PuzzleKind::Nonogram;
+                html!{
+                    <div class="gridbuilder">
+                        <div class="prompt">
+                            { format!("{}: Pick a puzzle, then fill in its givens and clues", step) }
+                        </div>
+                        <div class="problemtypeoptions">
+                            {
+                                for presets.iter().enumerate().map(|(i, (label, kind, size))| html! {
+                                    <div
+                                        class={
+                                            let mut classes = "type".to_string();
+                                            if puzzle.kind == *kind && (nonogram || puzzle.kind == PuzzleKind::KenKen || puzzle.rows == *size) {
+                                                classes.push_str(" selected");
+                                            }
+                                            if i == 0 {
+                                                classes.push_str(" left");
+                                            } else if i + 1 == presets.len() {
+                                                classes.push_str(" right");
+                                            } else {
+                                                classes.push_str(" center");
+                                            }
+                                            classes
+                                        }
+                                        onclick=&self.set_grid_kind(kind.clone(), *size)>
+                                        { label }
+                                    </div>
+                                })
+                            }
+                        </div>
+                        {
+                            if let PuzzleKind::Sudoku { .. } = puzzle.kind {
+                                html! {}
+                            } else {
+                                html! {
+                                    <div class="gridsize">
+                                        { "Size: " }
+                                        <input type="number" min="1" max=MAX_GRID_SIZE.to_string() value=puzzle.rows.to_string() oninput=&self.update_grid_size() />
+                                    </div>
+                                }
+                            }
+                        }
+                        <table class="gridpuzzle">
+                            {
+                                if nonogram {
+                                    html! {
+                                        <tr>
+                                            <td></td>
+                                            {
+                                                for (0..puzzle.cols).map(|col| html! {
+                                                    <td class="clue column">
+                                                        <input class="clueinput" type="text" placeholder="runs" oninput=&self.update_grid_clue(false, col) />
+                                                    </td>
+                                                })
+                                            }
+                                        </tr>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                            {
+                                for (0..puzzle.rows).map(|row| html! {
+                                    <tr>
+                                        {
+                                            if nonogram {
+                                                html! {
+                                                    <td class="clue row">
+                                                        <input class="clueinput" type="text" placeholder="runs" oninput=&self.update_grid_clue(true, row) />
+                                                    </td>
+                                                }
+                                            } else {
+                                                html! {}
+                                            }
+                                        }
+                                        {
+                                            for (0..puzzle.cols).map(|col| {
+                                                let region = puzzle.region(row, col);
+                                                let mut classes = "cell".to_string();
+                                                if col + 1 < puzzle.cols && puzzle.region(row, col + 1) != region {
+                                                    classes.push_str(" thickright");
+                                                }
+                                                if row + 1 < puzzle.rows && puzzle.region(row + 1, col) != region {
+                                                    classes.push_str(" thickbottom");
+                                                }
+                                                html! {
+                                                    <td class=classes>
+                                                        {
+                                                            if nonogram {
+                                                                html! {}
+                                                            } else {
+                                                                html! {
+                                                                    <input
+                                                                        type="text"
+                                                                        maxlength="2"
+                                                                        value=puzzle.given(row, col).map(|value| value.to_string()).unwrap_or_default()
+                                                                        oninput=&self.update_grid_cell(row, col) />
+                                                                }
+                                                            }
+                                                        }
+                                                    </td>
+                                                }
+                                            })
+                                        }
+                                    </tr>
+                                })
+                            }
+                        </table>
+                        {
+                            if puzzle.kind == PuzzleKind::KenKen {
+                                html! {
+                                    <div class="cages">
+                                        <div class="addchoice">
+                                            <input type="text" placeholder="Cells, operator and target, e.g. 1,1 1,2 + 3" oninput=&self.update_choice() />
+                                            <button class="add" onclick=&self.add_choice()>{ "Add cage" }</button>
+                                        </div>
+                                        {
+                                            for puzzle.constraints.iter().enumerate().filter_map(|(i, constraint)| match constraint {
+                                                GridConstraint::Cage { cells, op, target } => Some(html! {
+                                                    <div class="optionwrapper">
+                                                        <span class="optionmarker" onclick=&self.remove_choice(i)>{ "X" }</span>
+                                                        <div class="option">
+                                                            {
+                                                                format!(
+                                                                    "{}{} over {}",
+                                                                    target,
+                                                                    op.symbol(),
+                                                                    cells.iter().map(|(row, col)| format!("({}, {})", row + 1, col + 1)).collect::<Vec<_>>().join(" ")
+                                                                )
+                                                            }
+                                                        </div>
+                                                    </div>
+                                                }),
+                                                _ => None,
+                                            })
+                                        }
+                                    </div>
+                                }
+                            } else {
+                                html! {}
+                            }
+                        }
+                        <button class="add" onclick=&self.check_uniqueness()>{ "Check for a unique solution" }</button>
+                        <div class="uniqueness">
+                            {
+                                match solutions {
+                                    Some(0) => "This puzzle has no solution",
+                                    Some(1) => "This puzzle has exactly one solution",
+                                    Some(_) => "Warning: this puzzle has more than one solution",
+                                    None => "",
+                                }
+                            }
+                        </div>
+                    </div>
+                }
+            },
+            ProblemContentBuilder::MultiPart {
+                parts,
+                part_prompt,
+                part_weight,
+                current,
+            } => html!{
+                <div class="multipart">
+                    <div class="prompt">
+                        { format!("{}: Write each part, pick its answer type and add it", step) }
+                    </div>
+                    <div class="partsdisplay">
+                        {
+                            for parts.iter().enumerate().map(|(i, part)| html! {
+                                <div class="optionwrapper">
+                                    <span class="optionmarker" onclick=&self.remove_part(i)>{ "X" }</span>
+                                    <div class="option">
+                                        {
+                                            format!(
+                                                "({}) {} [{}, weight {}]",
+                                                (b'a' + i as u8) as char,
+                                                part.prompt,
+                                                serde_json::to_string(&part.content.get_type()).unwrap().replace(r#"""#, ""),
+                                                part.weight
+                                            )
+                                        }
+                                    </div>
+                                </div>
+                            })
+                        }
+                    </div>
+                    <div class="addpart">
+                        <input class="partpromptinput" type="text" placeholder="Prompt for this part" value=part_prompt oninput=&self.update_part_prompt() />
+                        <input class="partweightinput" type="number" min="0" step="any" value=part_weight.to_string() oninput=&self.update_part_weight() />
+                    </div>
+                    { self.view_type_options(&current.get_type(), true) }
+                    { self.view_content_builder(current, "Then") }
+                    <button class="add" onclick=&self.add_part()>{ "Add part" }</button>
+                </div>
+            },
+        }
     }
 }
 
@@ -178,24 +834,29 @@ impl Component for CreateComponent {
         let callback = link.callback(|_| CreateMsg::NoOp);
         let router = RouteAgent::bridge(callback);
 
-        Self {
+        let mut component = Self {
             link,
             props,
             router,
             fetch_service: FetchService::new(),
             ft: None,
+            topics_ft: None,
+            topics: vec![],
             builder: ProblemBuilder::default(),
             content_input_buffer: String::new(),
+            match_input_buffer: String::new(),
             new_tag_text: String::new(),
             error_message: String::new(),
-        }
+        };
+        component.topics_ft = Some(component.send_topics_request());
+        component
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             CreateMsg::NoOp => false,
-            CreateMsg::SetTopic(topic) => {
-                self.builder.topic = Some(topic);
+            CreateMsg::SetTopic(topic_id) => {
+                self.builder.topic_id = Some(topic_id);
                 true
             }
             CreateMsg::SetContent(content) => {
@@ -221,15 +882,17 @@ impl Component for CreateComponent {
                 false
             }
             CreateMsg::AddSolution(idx) => {
-                match &mut self.builder.content {
+                match self.builder.content.active_mut() {
                     ProblemContentBuilder::MultipleChoice { options, solution } => {
                         *solution = Some(idx)
                     }
+                    _ => (),
                 };
-                match &mut self.builder.content {
+                match self.builder.content.active_mut() {
                     ProblemContentBuilder::MultipleChoice { options, solution } => {
                         info!("{:?}", solution);
                     }
+                    _ => (),
                 };
                 true
             }
@@ -238,17 +901,40 @@ impl Component for CreateComponent {
                 false
             }
             CreateMsg::AddChoice => {
-                match &mut self.builder.content {
+                match self.builder.content.active_mut() {
                     ProblemContentBuilder::MultipleChoice { options, solution } => {
                         if !self.content_input_buffer.is_empty() {
                             options.push(self.content_input_buffer.clone());
                         }
                     }
+                    ProblemContentBuilder::Ordering { items, .. } => {
+                        if !self.content_input_buffer.is_empty() {
+                            items.push(self.content_input_buffer.clone());
+                        }
+                    }
+                    ProblemContentBuilder::Matching { pairs } => {
+                        if !self.content_input_buffer.is_empty() && !self.match_input_buffer.is_empty() {
+                            pairs.push((
+                                self.content_input_buffer.clone(),
+                                self.match_input_buffer.clone(),
+                            ));
+                        }
+                    }
+                    ProblemContentBuilder::Grid { puzzle, solutions } => {
+                        match parse_cage(&self.content_input_buffer) {
+                            Ok(cage) => {
+                                puzzle.constraints.push(cage);
+                                *solutions = None;
+                            }
+                            Err(error_message) => self.error_message = error_message,
+                        }
+                    }
+                    ProblemContentBuilder::MultiPart { .. } => (),
                 }
                 true
             }
             CreateMsg::RemoveChoice(idx) => {
-                match &mut self.builder.content {
+                match self.builder.content.active_mut() {
                     ProblemContentBuilder::MultipleChoice { options, solution } => {
                         if idx >= options.len() {
                             return false;
@@ -262,46 +948,225 @@ impl Component for CreateComponent {
                             }
                         }
                     }
+                    ProblemContentBuilder::Ordering { items, .. } => {
+                        if idx >= items.len() {
+                            return false;
+                        }
+                        items.remove(idx);
+                    }
+                    ProblemContentBuilder::Matching { pairs } => {
+                        if idx >= pairs.len() {
+                            return false;
+                        }
+                        pairs.remove(idx);
+                    }
+                    ProblemContentBuilder::Grid { puzzle, solutions } => {
+                        if idx >= puzzle.constraints.len() {
+                            return false;
+                        }
+                        puzzle.constraints.remove(idx);
+                        *solutions = None;
+                    }
+                    ProblemContentBuilder::MultiPart { .. } => (),
+                }
+                true
+            }
+            CreateMsg::MoveChoice(idx, up) => {
+                if let ProblemContentBuilder::Ordering { items, .. } = self.builder.content.active_mut() {
+                    let target = if up { idx.checked_sub(1) } else { Some(idx + 1) };
+                    match target {
+                        Some(target) if target < items.len() && idx < items.len() => {
+                            items.swap(idx, target)
+                        }
+                        _ => return false,
+                    }
+                }
+                true
+            }
+            CreateMsg::UpdateMatch(match_text) => {
+                self.match_input_buffer = match_text;
+                false
+            }
+            CreateMsg::TogglePartialCredit => {
+                if let ProblemContentBuilder::Ordering { partial_credit, .. } =
+                    self.builder.content.active_mut()
+                {
+                    *partial_credit = !*partial_credit;
+                }
+                true
+            }
+            CreateMsg::SetGridKind(kind, size) => {
+                *self.builder.content.active_mut() = ProblemContentBuilder::grid(kind, size);
+                true
+            }
+            CreateMsg::UpdateGridSize(size) => {
+                if let ProblemContentBuilder::Grid { puzzle, solutions } = self.builder.content.active_mut() {
+                    match size.parse::<usize>() {
+                        Ok(size) if size >= 1 && size <= MAX_GRID_SIZE => {
+                            puzzle.rows = size;
+                            puzzle.cols = size;
+                            puzzle.givens.retain(|(row, col, _)| *row < size && *col < size);
+                            puzzle.constraints.retain(|constraint| match constraint {
+                                GridConstraint::RowClue { row, .. } => *row < size,
+                                GridConstraint::ColumnClue { col, .. } => *col < size,
+                                GridConstraint::Cage { cells, .. } => {
+                                    cells.iter().all(|(row, col)| *row < size && *col < size)
+                                }
+                            });
+                            *solutions = None;
+                            self.error_message = "".into();
+                        }
+                        _ => {
+                            self.error_message =
+                                format!("The grid size must be between 1 and {}", MAX_GRID_SIZE)
+                        }
+                    }
+                }
+                true
+            }
+            CreateMsg::UpdateGridCell(row, col, value) => {
+                if let ProblemContentBuilder::Grid { puzzle, solutions } = self.builder.content.active_mut() {
+                    puzzle.givens.retain(|(given_row, given_col, _)| (*given_row, *given_col) != (row, col));
+                    if let Ok(value) = value.trim().parse::<u32>() {
+                        puzzle.givens.push((row, col, value));
+                    }
+                    *solutions = None;
+                }
+                true
+            }
+            CreateMsg::UpdateGridClue(is_row, idx, clue) => {
+                if let ProblemContentBuilder::Grid { puzzle, solutions } = self.builder.content.active_mut() {
+                    let runs: Vec<usize> = clue
+                        .split(|c: char| c.is_whitespace() || c == ',')
+                        .filter_map(|run| run.parse().ok())
+                        .collect();
+                    puzzle.constraints.retain(|constraint| match constraint {
+                        GridConstraint::RowClue { row, .. } => !is_row || *row != idx,
+                        GridConstraint::ColumnClue { col, .. } => is_row || *col != idx,
+                        GridConstraint::Cage { .. } => true,
+                    });
+                    puzzle.constraints.push(if is_row {
+                        GridConstraint::RowClue { row: idx, runs }
+                    } else {
+                        GridConstraint::ColumnClue { col: idx, runs }
+                    });
+                    *solutions = None;
+                }
+                false
+            }
+            CreateMsg::CheckUniqueness => {
+                if let ProblemContentBuilder::Grid { puzzle, solutions } = self.builder.content.active_mut() {
+                    match puzzle.validate() {
+                        Ok(()) => *solutions = Some(puzzle.count_solutions(2)),
+                        Err(error_message) => self.error_message = error_message,
+                    }
+                }
+                true
+            }
+            CreateMsg::SetPartContent(content) => {
+                if let ProblemContentBuilder::MultiPart { current, .. } = &mut self.builder.content {
+                    *current = Box::new(content);
+                }
+                true
+            }
+            CreateMsg::UpdatePartPrompt(prompt) => {
+                if let ProblemContentBuilder::MultiPart { part_prompt, .. } =
+                    &mut self.builder.content
+                {
+                    *part_prompt = prompt;
+                }
+                false
+            }
+            CreateMsg::UpdatePartWeight(weight) => {
+                if let ProblemContentBuilder::MultiPart { part_weight, .. } =
+                    &mut self.builder.content
+                {
+                    match weight.parse::<f32>() {
+                        Ok(weight) if weight > 0.0 => {
+                            *part_weight = weight;
+                            self.error_message = "".into();
+                        }
+                        _ => self.error_message = "A part's weight must be a positive number".into(),
+                    }
+                }
+                true
+            }
+            CreateMsg::AddPart => {
+                if let ProblemContentBuilder::MultiPart {
+                    parts,
+                    part_prompt,
+                    part_weight,
+                    current,
+                } = &mut self.builder.content
+                {
+                    if part_prompt.is_empty() {
+                        self.error_message = "Please enter a prompt for this part".into();
+                        return true;
+                    }
+                    let seed_text = format!("{}{}", self.builder.prompt, part_prompt);
+                    match current.build(&seed_text) {
+                        Ok(content) => {
+                            parts.push(Part {
+                                prompt: part_prompt.clone(),
+                                content,
+                                weight: *part_weight,
+                            });
+                            part_prompt.clear();
+                            *current = Box::new(ProblemContentBuilder::default_from_type(
+                                &current.get_type(),
+                            ));
+                            self.error_message = "".into();
+                        }
+                        Err(error_message) => self.error_message = error_message,
+                    }
+                }
+                true
+            }
+            CreateMsg::RemovePart(idx) => {
+                if let ProblemContentBuilder::MultiPart { parts, .. } = &mut self.builder.content {
+                    if idx >= parts.len() {
+                        return false;
+                    }
+                    parts.remove(idx);
                 }
                 true
             }
             CreateMsg::Finish => {
-                if let Some(topic) = &self.builder.topic {
+                if let Some(topic_id) = self.builder.topic_id {
                     if self.builder.prompt.is_empty() {
                         self.error_message = "Please enter a prompt".into();
                         return true;
                     }
-                    match &self.builder.content {
-                        ProblemContentBuilder::MultipleChoice { options, solution } => {
-                            if options.len() <= 1 {
-                                self.error_message =
-                                    "Please provide at least 2 answer options".into();
-                                return true;
-                            }
-                            if let Some(solution) = *solution {
-                                let req = NewProblem {
-                                    owner_id: self.props.user_id,
-                                    topic: topic.clone(),
-                                    tags: self.builder.tags.clone(),
-                                    prompt: self.builder.prompt.clone(),
-                                    content: ProblemContent::MultipleChoice {
-                                        options: options.clone(),
-                                        solution,
-                                    },
-                                    explanation: "".to_string() // TODO
-                                };
-                                self.error_message = "".into();
-                                self.ft = Some(self.send_creation_request(req));
-                            } else {
-                                self.error_message = "Please select a solution by clicking on one of the response options".into();
-                            }
+                    let content = match self.builder.content.build(&self.builder.prompt) {
+                        Ok(content) => content,
+                        Err(error_message) => {
+                            self.error_message = error_message;
+                            return true;
                         }
-                    }
+                    };
+                    let req = NewProblem {
+                        owner_id: self.props.user_id,
+                        topic_id,
+                        tags: self.builder.tags.clone(),
+                        prompt: self.builder.prompt.clone(),
+                        content,
+                        explanation: "".to_string() // TODO
+                    };
+                    self.error_message = "".into();
+                    self.ft = Some(self.send_creation_request(req));
                 } else {
                     self.error_message = "You must specify a topic for your problem".into();
                 }
                 true
             }
+            CreateMsg::TopicsLoaded(topics) => {
+                self.topics = topics;
+                true
+            }
+            CreateMsg::TopicsFailed(error_message) => {
+                self.error_message = format!("Could not load topics: {}", error_message);
+                true
+            }
             CreateMsg::CreationFailure(error_message) => {
                 self.error_message = error_message;
                 true
@@ -315,8 +1180,6 @@ impl Component for CreateComponent {
     }
 
     fn view(&self) -> Html {
-        let topics = vec![Topic::Math, Topic::Trivia, Topic::Logic];
-        let problem_types = vec![ProblemType::MultipleChoice, ProblemType::FreeResponse, ProblemType::Checklist];
         html! {
             <div class="createproblemwrapper">
                 <div class="createproblem">
@@ -328,36 +1191,9 @@ impl Component for CreateComponent {
                     </div>
                     <div class="selector">
                         <div class="prompt">
-                            { "Step 1: Pick a general topic" }
-                        </div>
-                        <div class="topicoptions">
-                            {
-                                for topics.iter().enumerate().map(|(i, topic)| {
-                                    html! {
-                                        <div
-                                            class={
-                                                let mut classes = "topic".to_string();
-                                                if self.builder.topic == Some(topic.clone()) {
-                                                    classes.push_str(" selected")
-                                                }
-                                                if i == 0 {
-                                                    classes.push_str(" left")
-                                                } else if i + 1 == topics.len() {
-                                                    classes.push_str(" right")
-                                                } else {
-                                                    classes.push_str(" center")
-                                                }
-                                                classes
-                                            }
-                                            onclick=&self.set_topic(topic.clone())>
-                                            {
-                                                serde_json::to_string(&topic).unwrap().replace(r#"""#, "").replace("Trivia", "Linguistics")
-                                            }
-                                        </div>
-                                    }
-                                })
-                            }
+                            { "Step 1: Pick a topic" }
                         </div>
+                        { self.view_topic_tree(None) }
                     </div>
                     <div class="selector">
                         <div class="prompt">
@@ -385,74 +1221,11 @@ impl Component for CreateComponent {
                         <div class="prompt">
                             { "Step 4: Select an answer type" }
                         </div>
-                        <div class="problemtypeoptions">
-                            {
-                                for problem_types.iter().enumerate().map(|(i, p_type)| {
-                                    html! {
-                                        <div
-                                            class={
-                                                let mut classes = "type".to_string();
-                                                if self.builder.content.get_type() == *p_type {
-                                                    classes.push_str(" selected");
-                                                }
-                                                if i == 0 {
-                                                    classes.push_str(" left");
-                                                } else if i + 1 == problem_types.len() {
-                                                    classes.push_str(" right");
-                                                } else {
-                                                    classes.push_str(" center");
-                                                }
-                                                classes
-                                            }
-                                            onclick={
-                                                if i == 0 {
-                                                    self.set_content(ProblemContentBuilder::default_from_type(&p_type))
-                                                } else {
-                                                    self.link.callback(|_| CreateMsg::NoOp)
-                                                }
-                                            }
-                                            >
-                                            {
-                                                serde_json::to_string(&p_type).unwrap().replace(r#"""#, "")
-                                            }
-                                        </div>
-                                    }
-                                })
-                            }
-                        </div>
+                        { self.view_type_options(&self.builder.content.get_type(), false) }
                     </div>
                     <div class="selector">
                         {
-                            match &self.builder.content {
-                                ProblemContentBuilder::MultipleChoice { options, solution } => html!{
-                                    <div class="multiplechoice">
-                                        <div class="prompt">
-                                            { "Step 5: Add choices and click the correct one" }
-                                        </div>
-                                        <div class="addchoice">
-                                            <input type="text" oninput=&self.update_choice() />
-                                            <button class="add" onclick=&self.add_choice()>{ "Add choice" }</button>
-                                        </div>
-                                        <div class="multiplechoicedisplay">
-                                            {
-                                                for options.iter().enumerate().map(|(i, option)| html! {
-                                                    <div class="optionwrapper">
-                                                        <span class="optionmarker" onclick=&self.remove_choice(i)>{ "X" }</span>
-                                                        <div onclick=&self.add_solution(i) class={
-                                                            if Some(i) == *solution {
-                                                                "option selected"
-                                                            } else {
-                                                                "option"
-                                                            }
-                                                        }>{option}</div>
-                                                    </div>
-                                                })
-                                            }
-                                        </div>
-                                    </div>
-                                },
-                                _ => unimplemented!("Not all problem types can be created rn")
-                            }
+                            self.view_content_builder(&self.builder.content, "Step 5")
                         }
                     </div>
                     <button class="submitproblem" onclick=&self.finish_problem()>
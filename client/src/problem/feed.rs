@@ -1,5 +1,8 @@
-use crate::{app::API_URL, problem::wrapper::ProblemComponent};
-use common::{user::User, problems::{Problem, ProblemContent}};
+use crate::{
+    app::{fetch_options, API_URL},
+    problem::wrapper::ProblemComponent,
+};
+use common::{user::{Permission, User}, problems::{Problem, ProblemContent}};
 use log::*;
 use yew::{
     format::{Json, Nothing},
@@ -41,7 +44,9 @@ impl FeedComponent {
         let request = Request::get(&self.props.feed_endpoint)
             .body(Nothing)
             .unwrap();
-        self.fetch_service.fetch(request, callback).unwrap()
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
     }
 }
 
@@ -86,7 +91,7 @@ impl Component for FeedComponent {
                                 <div class="problems">
                                     {
                                         for self.problems.iter().map(|problem_id| html! {
-                                            <ProblemComponent problemid={ problem_id } recommended=&self.props.user.recommended_ids.contains(&problem_id) />
+                                            <ProblemComponent problemid={ problem_id } user_id=self.props.user.id recommended=&self.props.user.recommended_ids.contains(&problem_id) can_moderate=self.props.user.can(Permission::ModerateProblems) />
                                         })
                                     }
                                 </div>
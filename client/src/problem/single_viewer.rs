@@ -21,6 +21,7 @@ pub enum ViewerMsg {
 #[derive(Debug, Clone, Properties)]
 pub struct ViewerProps {
     pub problemid: i32,
+    pub user_id: i32,
     pub recommended: bool,
 }
 
@@ -65,7 +66,7 @@ impl Component for SingleViewerComponent {
     fn view(&self) -> Html {
         html!{
             <div class="singleviewer">
-                <ProblemComponent problemid=self.props.problemid recommended=self.props.recommended />
+                <ProblemComponent problemid=self.props.problemid user_id=self.props.user_id recommended=self.props.recommended />
                 <button onclick=&self.change_route("/create".to_string())>{ "Create another problem" }</button>
             </div>
         }
@@ -1,4 +1,7 @@
-use common::problems;
+use common::{
+    grading::{Grade, Submission},
+    problems,
+};
 use log::*;
 use yew::{
     format::{Json, Nothing},
@@ -17,6 +20,9 @@ pub enum ChecklistMsg {
 pub struct ChecklistProps {
     pub options: Vec<String>,
     pub solution: Vec<usize>,
+    pub onsubmission: Callback<Submission>,
+    #[prop_or_default]
+    pub grade: Option<Grade>,
 }
 
 pub struct ChecklistComponent {
@@ -47,11 +53,24 @@ impl Component for ChecklistComponent {
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
-            ChecklistMsg::ChoiceSelected(idx) => self.choices[idx] = !self.choices[idx],
+            ChecklistMsg::ChoiceSelected(idx) => {
+                self.choices[idx] = !self.choices[idx];
+                let selected = (0..self.choices.len())
+                    .filter(|i| self.choices[*i])
+                    .collect();
+                self.props
+                    .onsubmission
+                    .emit(Submission::Checklist(selected));
+            }
         }
         true
     }
 
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
     fn view(&self) -> Html {
         html! {
             <div class="checklist">
@@ -60,11 +79,19 @@ impl Component for ChecklistComponent {
                         <div class="optionwrapper">
                             <span class="optionmarker">{ ">" }</span>
                             <div onclick=self.select_choice(i) class={
+                                let mut classes = "option".to_string();
                                 if self.choices[i] {
-                                    "option selected"
-                                } else {
-                                    "option"
+                                    classes.push_str(" selected");
+                                }
+                                // Highlight each option once the submission is graded
+                                if let Some(item) = self.props.grade.as_ref().and_then(|grade| grade.per_item.get(i)) {
+                                    if item.correct {
+                                        classes.push_str(" correct");
+                                    } else {
+                                        classes.push_str(" incorrect");
+                                    }
                                 }
+                                classes
                             }>{option}</div>
                         </div>
                     })
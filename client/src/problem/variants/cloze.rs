@@ -0,0 +1,154 @@
+use crate::problem::wrapper::LatexablePart;
+use common::{
+    grading::{Grade, Submission},
+    problems::{self, Blank, ClozeSegment},
+};
+use yew::prelude::*;
+
+pub enum ClozeMsg {
+    BlankInput(usize, String),
+    BlankSelected(usize, Option<usize>),
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct ClozeProps {
+    pub prompt: Vec<LatexablePart>,
+    pub blanks: Vec<Blank>,
+    pub onsubmission: Callback<Submission>,
+    #[prop_or_default]
+    pub grade: Option<Grade>,
+}
+
+pub struct ClozeComponent {
+    link: ComponentLink<Self>,
+    props: ClozeProps,
+    answers: Vec<Option<Submission>>,
+    error_message: String,
+}
+
+impl ClozeComponent {
+    pub fn blank_input(&self, blank: usize) -> Callback<InputData> {
+        self.link
+            .callback(move |input: InputData| ClozeMsg::BlankInput(blank, input.value))
+    }
+
+    pub fn select_option(&self, blank: usize) -> Callback<ChangeData> {
+        self.link.callback(move |data: ChangeData| {
+            let option = match data {
+                ChangeData::Select(select) => select.value().parse().ok(),
+                _ => None,
+            };
+            ClozeMsg::BlankSelected(blank, option)
+        })
+    }
+
+    fn view_blank(&self, idx: usize) -> Html {
+        let mut classes = "blank".to_string();
+        if let Some(graded) = self.props.grade.as_ref().and_then(|grade| grade.per_item.get(idx)) {
+            if graded.correct {
+                classes.push_str(" correct");
+            } else {
+                classes.push_str(" incorrect");
+            }
+        }
+        match self.props.blanks.get(idx) {
+            Some(Blank::FreeResponse { .. }) => html! {
+                <input class=classes type="text" oninput=self.blank_input(idx) />
+            },
+            Some(Blank::Dropdown { options, .. }) => {
+                let selected = match &self.answers[idx] {
+                    Some(Submission::MultipleChoice(choice)) => Some(*choice),
+                    _ => None,
+                };
+                html! {
+                    <select class=classes onchange=self.select_option(idx)>
+                        <option value="" selected=selected.is_none()>{ "—" }</option>
+                        {
+                            for options.iter().enumerate().map(|(i, option)| html! {
+                                <option value={ i.to_string() } selected=selected == Some(i)>{ option }</option>
+                            })
+                        }
+                    </select>
+                }
+            }
+            None => html! {
+                <span class="errorbox">{ format!("[missing blank {}]", idx + 1) }</span>
+            },
+        }
+    }
+}
+
+impl Component for ClozeComponent {
+    type Message = ClozeMsg;
+    type Properties = ClozeProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let answers = vec![None; props.blanks.len()];
+        Self {
+            link,
+            props,
+            answers,
+            error_message: String::new(),
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            ClozeMsg::BlankInput(idx, input) => {
+                self.error_message = String::new();
+                if let Some(Blank::FreeResponse { restrictions, .. }) = self.props.blanks.get(idx) {
+                    for restriction in restrictions {
+                        if let Err(error) = restriction.check(input.clone()) {
+                            self.error_message = format!("Blank {}: {}", idx + 1, error);
+                            break;
+                        }
+                    }
+                }
+                self.answers[idx] = Some(Submission::FreeResponse(input));
+            }
+            ClozeMsg::BlankSelected(idx, option) => {
+                self.answers[idx] = option.map(Submission::MultipleChoice);
+            }
+        }
+        self.props
+            .onsubmission
+            .emit(Submission::Cloze(self.answers.clone()));
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div class="cloze">
+                <div class="prompt">
+                    {
+                        for self.props.prompt.iter().map(|part| match part {
+                            LatexablePart::Text(text) => html! {
+                                <>
+                                    {
+                                        for problems::cloze_segments(text).into_iter().map(|segment| match segment {
+                                            ClozeSegment::Text(text) => html! {
+                                                <span class="part">{ text }</span>
+                                            },
+                                            ClozeSegment::Blank(idx) => self.view_blank(idx),
+                                        })
+                                    }
+                                </>
+                            },
+                            LatexablePart::Latex(text) => html! {
+                                <span class="part rendermath">{ text }</span>
+                            },
+                        })
+                    }
+                </div>
+                <div class="errorbox">
+                    { &self.error_message }
+                </div>
+            </div>
+        }
+    }
+}
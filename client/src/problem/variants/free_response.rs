@@ -1,4 +1,4 @@
-use common::problems;
+use common::{grading::Submission, problems};
 use log::*;
 use serde::{Deserialize, Serialize};
 use yew::{
@@ -18,6 +18,7 @@ pub enum FreeRespMsg {
 pub struct FreeRespProps {
     pub restrictions: Vec<problems::FreeResponseRestriction>,
     pub solution: Vec<problems::FreeResponseSolution>,
+    pub onsubmission: Callback<Submission>,
 }
 
 pub struct FreeRespComponent {
@@ -51,6 +52,9 @@ impl Component for FreeRespComponent {
         match msg {
             FreeRespMsg::InputChanged(input) => {
                 self.current_input = input.clone();
+                self.props
+                    .onsubmission
+                    .emit(Submission::FreeResponse(input.clone()));
                 for restriction in &self.props.restrictions {
                     if let Err(error) = restriction.check(input.clone()) {
                         self.error_message = error;
@@ -63,6 +67,11 @@ impl Component for FreeRespComponent {
         }
     }
 
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        false
+    }
+
     fn view(&self) -> Html {
         html! {
             <div class="freeresponse">
@@ -0,0 +1,207 @@
+use common::{
+    grading::{Grade, Submission},
+    grid::{Cells, GridConstraint, GridPuzzle, PuzzleKind},
+};
+use yew::prelude::*;
+
+pub enum GridMsg {
+    SetCell(usize, usize, String),
+    ToggleCell(usize, usize),
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct GridProps {
+    pub puzzle: GridPuzzle,
+    pub onsubmission: Callback<Submission>,
+    #[prop_or_default]
+    pub grade: Option<Grade>,
+}
+
+pub struct GridComponent {
+    link: ComponentLink<Self>,
+    props: GridProps,
+    cells: Cells,
+}
+
+impl GridComponent {
+    pub fn set_cell(&self, row: usize, col: usize) -> Callback<InputData> {
+        self.link
+            .callback(move |input: InputData| GridMsg::SetCell(row, col, input.value))
+    }
+
+    pub fn toggle_cell(&self, row: usize, col: usize) -> Callback<MouseEvent> {
+        self.link.callback(move |_| GridMsg::ToggleCell(row, col))
+    }
+
+    fn clue(&self, row: Option<usize>, col: Option<usize>) -> String {
+        self.props
+            .puzzle
+            .constraints
+            .iter()
+            .find_map(|constraint| match constraint {
+                GridConstraint::RowClue { row: clue_row, runs } if Some(*clue_row) == row => Some(runs),
+                GridConstraint::ColumnClue { col: clue_col, runs } if Some(*clue_col) == col => Some(runs),
+                _ => None,
+            })
+            .map(|runs| {
+                runs.iter()
+                    .map(|run| run.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default()
+    }
+
+    /// The label shown in the first cell of a KenKen cage, e.g. `12×`.
+    fn cage_label(&self, row: usize, col: usize) -> Option<String> {
+        self.props
+            .puzzle
+            .constraints
+            .iter()
+            .find_map(|constraint| match constraint {
+                GridConstraint::Cage { cells, op, target } if cells.first() == Some(&(row, col)) => {
+                    Some(if cells.len() == 1 {
+                        target.to_string()
+                    } else {
+                        format!("{}{}", target, op.symbol())
+                    })
+                }
+                _ => None,
+            })
+    }
+
+    fn view_cell(&self, row: usize, col: usize) -> Html {
+        let puzzle = &self.props.puzzle;
+        let mut classes = "cell".to_string();
+        let region = puzzle.region(row, col);
+        if col + 1 < puzzle.cols && puzzle.region(row, col + 1) != region {
+            classes.push_str(" thickright");
+        }
+        if row + 1 < puzzle.rows && puzzle.region(row + 1, col) != region {
+            classes.push_str(" thickbottom");
+        }
+        let given = puzzle.given(row, col);
+        if given.is_some() {
+            classes.push_str(" given");
+        }
+
+        if puzzle.kind == PuzzleKind::Nonogram {
+            if self.cells[row][col] == Some(1) {
+                classes.push_str(" shaded");
+            }
+            return html! {
+                <td class=classes onclick=self.toggle_cell(row, col)></td>
+            };
+        }
+        html! {
+            <td class=classes>
+                {
+                    if let Some(label) = self.cage_label(row, col) {
+                        html! { <span class="cagelabel">{ label }</span> }
+                    } else {
+                        html! {}
+                    }
+                }
+                {
+                    if let Some(given) = given {
+                        html! { <span class="value">{ given }</span> }
+                    } else {
+                        html! {
+                            <input
+                                type="text"
+                                maxlength="2"
+                                value=self.cells[row][col].map(|value| value.to_string()).unwrap_or_default()
+                                oninput=self.set_cell(row, col) />
+                        }
+                    }
+                }
+            </td>
+        }
+    }
+}
+
+impl Component for GridComponent {
+    type Message = GridMsg;
+    type Properties = GridProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let cells = props.puzzle.initial_cells();
+        Self { link, props, cells }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            GridMsg::SetCell(row, col, input) => {
+                self.cells[row][col] = input.trim().parse().ok();
+            }
+            GridMsg::ToggleCell(row, col) => {
+                if self.props.puzzle.given(row, col).is_some() {
+                    return false;
+                }
+                self.cells[row][col] = match self.cells[row][col] {
+                    Some(1) => None,
+                    _ => Some(1),
+                };
+            }
+        }
+        self.props
+            .onsubmission
+            .emit(Submission::Grid(self.cells.clone()));
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let puzzle = &self.props.puzzle;
+        let nonogram = puzzle.kind == PuzzleKind::Nonogram;
+        html! {
+            <div class="grid">
+                <table class="gridpuzzle">
+                    {
+                        if nonogram {
+                            html! {
+                                <tr>
+                                    <td></td>
+                                    {
+                                        for (0..puzzle.cols).map(|col| html! {
+                                            <td class="clue column">{ self.clue(None, Some(col)) }</td>
+                                        })
+                                    }
+                                </tr>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    {
+                        for (0..puzzle.rows).map(|row| html! {
+                            <tr>
+                                {
+                                    if nonogram {
+                                        html! { <td class="clue row">{ self.clue(Some(row), None) }</td> }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                                { for (0..puzzle.cols).map(|col| self.view_cell(row, col)) }
+                            </tr>
+                        })
+                    }
+                </table>
+                {
+                    // Say which rule was broken, since there's no single answer to show
+                    match (&self.props.grade, puzzle.check(&self.cells)) {
+                        (Some(grade), Err(error)) if !grade.is_correct() => html! {
+                            <div class="errorbox">{ error }</div>
+                        },
+                        _ => html! {},
+                    }
+                }
+            </div>
+        }
+    }
+}
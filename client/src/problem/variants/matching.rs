@@ -0,0 +1,107 @@
+use common::{
+    grading::{Grade, Submission},
+    problems,
+};
+use yew::prelude::*;
+
+pub enum MatchingMsg {
+    MatchSelected(usize, Option<usize>),
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct MatchingProps {
+    pub problemid: i32,
+    pub user_id: i32,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+    pub onsubmission: Callback<Submission>,
+    #[prop_or_default]
+    pub grade: Option<Grade>,
+}
+
+pub struct MatchingComponent {
+    link: ComponentLink<Self>,
+    props: MatchingProps,
+    right_order: Vec<usize>,
+    matches: Vec<Option<usize>>,
+}
+
+impl MatchingComponent {
+    pub fn select_match(&self, left: usize) -> Callback<ChangeData> {
+        self.link.callback(move |data: ChangeData| {
+            let right = match data {
+                ChangeData::Select(select) => select.value().parse().ok(),
+                _ => None,
+            };
+            MatchingMsg::MatchSelected(left, right)
+        })
+    }
+}
+
+impl Component for MatchingComponent {
+    type Message = MatchingMsg;
+    type Properties = MatchingProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let right_order =
+            problems::display_order(props.user_id, props.problemid, props.right.len());
+        let matches = vec![None; props.left.len()];
+        Self {
+            link,
+            props,
+            right_order,
+            matches,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            MatchingMsg::MatchSelected(left, right) => {
+                self.matches[left] = right;
+                self.props
+                    .onsubmission
+                    .emit(Submission::Matching(self.matches.clone()));
+            }
+        }
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div class="matching">
+                {
+                    for self.props.left.iter().enumerate().map(|(i, term)| html! {
+                        <div class={
+                            let mut classes = "matchrow".to_string();
+                            if let Some(graded) = self.props.grade.as_ref().and_then(|grade| grade.per_item.get(i)) {
+                                if graded.correct {
+                                    classes.push_str(" correct");
+                                } else {
+                                    classes.push_str(" incorrect");
+                                }
+                            }
+                            classes
+                        }>
+                            <span class="term">{ term }</span>
+                            <select onchange=self.select_match(i)>
+                                <option value="" selected=self.matches[i].is_none()>{ "—" }</option>
+                                {
+                                    for self.right_order.iter().map(|right| html! {
+                                        <option value={ right.to_string() } selected=self.matches[i] == Some(*right)>
+                                            { &self.props.right[*right] }
+                                        </option>
+                                    })
+                                }
+                            </select>
+                        </div>
+                    })
+                }
+            </div>
+        }
+    }
+}
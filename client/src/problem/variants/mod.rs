@@ -1,3 +1,8 @@
 pub mod free_response;
 pub mod multiple_choice;
 pub mod checklist;
+pub mod ordering;
+pub mod matching;
+pub mod cloze;
+pub mod grid;
+pub mod translation;
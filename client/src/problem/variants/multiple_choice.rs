@@ -1,4 +1,4 @@
-use common::problems;
+use common::{grading::Submission, problems};
 use log::*;
 use yew::{
     format::{Json, Nothing},
@@ -18,6 +18,7 @@ pub enum MultChoiceMsg {
 pub struct MultChoiceProps {
     pub options: Vec<String>,
     pub solution: usize,
+    pub onsubmission: Callback<Submission>,
 }
 
 pub struct MultChoiceComponent {
@@ -49,11 +50,19 @@ impl Component for MultChoiceComponent {
         match msg {
             MultChoiceMsg::ChoiceSelected(idx) => {
                 self.choice = Some(idx);
+                self.props
+                    .onsubmission
+                    .emit(Submission::MultipleChoice(idx));
             }
         }
         true
     }
 
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
     fn view(&self) -> Html {
         html! {
             <div class="multiplechoice">
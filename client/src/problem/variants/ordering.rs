@@ -0,0 +1,100 @@
+use common::{
+    grading::{Grade, Submission},
+    problems,
+};
+use yew::prelude::*;
+
+pub enum OrderingMsg {
+    MoveItem(usize, bool),
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct OrderingProps {
+    pub problemid: i32,
+    pub user_id: i32,
+    pub items: Vec<String>,
+    pub onsubmission: Callback<Submission>,
+    #[prop_or_default]
+    pub grade: Option<Grade>,
+}
+
+pub struct OrderingComponent {
+    link: ComponentLink<Self>,
+    props: OrderingProps,
+    order: Vec<usize>,
+}
+
+impl OrderingComponent {
+    pub fn move_item(&self, position: usize, up: bool) -> Callback<MouseEvent> {
+        self.link
+            .callback(move |_| OrderingMsg::MoveItem(position, up))
+    }
+}
+
+impl Component for OrderingComponent {
+    type Message = OrderingMsg;
+    type Properties = OrderingProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let order = problems::display_order(props.user_id, props.problemid, props.items.len());
+        Self { link, props, order }
+    }
+
+    fn mounted(&mut self) -> ShouldRender {
+        // Leaving the items as they are shown is an answer too
+        self.props
+            .onsubmission
+            .emit(Submission::Ordering(self.order.clone()));
+        false
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            OrderingMsg::MoveItem(position, up) => {
+                let target = if up {
+                    position.checked_sub(1)
+                } else {
+                    Some(position + 1).filter(|target| *target < self.order.len())
+                };
+                if let Some(target) = target {
+                    self.order.swap(position, target);
+                    self.props
+                        .onsubmission
+                        .emit(Submission::Ordering(self.order.clone()));
+                }
+            }
+        }
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div class="ordering">
+                {
+                    for self.order.iter().enumerate().map(|(position, item)| html! {
+                        <div class="optionwrapper">
+                            <span class="optionmarker" onclick=self.move_item(position, true)>{ "↑" }</span>
+                            <span class="optionmarker" onclick=self.move_item(position, false)>{ "↓" }</span>
+                            <div class={
+                                let mut classes = "option".to_string();
+                                if let Some(graded) = self.props.grade.as_ref().and_then(|grade| grade.per_item.get(position)) {
+                                    if graded.correct {
+                                        classes.push_str(" correct");
+                                    } else {
+                                        classes.push_str(" incorrect");
+                                    }
+                                }
+                                classes
+                            }>{ &self.props.items[*item] }</div>
+                        </div>
+                    })
+                }
+            </div>
+        }
+    }
+}
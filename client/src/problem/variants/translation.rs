@@ -0,0 +1,110 @@
+use common::{
+    grading::{Grade, Submission},
+    problems::TranslationItem,
+};
+use yew::prelude::*;
+
+pub enum TranslationMsg {
+    InputChanged(usize, String),
+}
+
+#[derive(Debug, Clone, Properties)]
+pub struct TranslationProps {
+    pub languages: (String, String),
+    pub examples: Vec<(String, String)>,
+    pub items: Vec<TranslationItem>,
+    pub onsubmission: Callback<Submission>,
+    #[prop_or_default]
+    pub grade: Option<Grade>,
+}
+
+pub struct TranslationComponent {
+    link: ComponentLink<Self>,
+    props: TranslationProps,
+    translations: Vec<Option<String>>,
+}
+
+impl TranslationComponent {
+    pub fn input_changed(&self, idx: usize) -> Callback<InputData> {
+        self.link
+            .callback(move |input: InputData| TranslationMsg::InputChanged(idx, input.value))
+    }
+}
+
+impl Component for TranslationComponent {
+    type Message = TranslationMsg;
+    type Properties = TranslationProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let translations = vec![None; props.items.len()];
+        Self {
+            link,
+            props,
+            translations,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            TranslationMsg::InputChanged(idx, input) => {
+                self.translations[idx] = Some(input).filter(|input| !input.trim().is_empty());
+                self.props
+                    .onsubmission
+                    .emit(Submission::Translation(self.translations.clone()));
+            }
+        }
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        self.props = props;
+        true
+    }
+
+    fn view(&self) -> Html {
+        let (first, second) = &self.props.languages;
+        html! {
+            <div class="translation">
+                <table class="examples">
+                    <tr>
+                        <th>{ first }</th>
+                        <th>{ second }</th>
+                    </tr>
+                    {
+                        for self.props.examples.iter().map(|(source, target)| html! {
+                            <tr>
+                                <td>{ source }</td>
+                                <td>{ target }</td>
+                            </tr>
+                        })
+                    }
+                </table>
+                <table class="items">
+                    {
+                        for self.props.items.iter().enumerate().map(|(i, item)| html! {
+                            <tr class={
+                                let mut classes = "item".to_string();
+                                if let Some(graded) = self.props.grade.as_ref().and_then(|grade| grade.per_item.get(i)) {
+                                    if graded.correct {
+                                        classes.push_str(" correct");
+                                    } else {
+                                        classes.push_str(" incorrect");
+                                    }
+                                }
+                                classes
+                            }>
+                                <td class="itemtext">{ &item.text }</td>
+                                <td class="direction">
+                                    { format!("→ {}", if item.reverse { first } else { second }) }
+                                </td>
+                                <td>
+                                    <input type="text" oninput=self.input_changed(i) />
+                                </td>
+                            </tr>
+                        })
+                    }
+                </table>
+            </div>
+        }
+    }
+}
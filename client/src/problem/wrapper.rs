@@ -1,11 +1,18 @@
 use crate::{
-    app::API_URL,
+    app::{fetch_options, API_URL},
     problem::variants::{
-        checklist::ChecklistComponent, free_response::FreeRespComponent,
-        multiple_choice::MultChoiceComponent,
+        checklist::ChecklistComponent, cloze::ClozeComponent, free_response::FreeRespComponent,
+        grid::GridComponent, matching::MatchingComponent, multiple_choice::MultChoiceComponent,
+        ordering::OrderingComponent, translation::TranslationComponent,
     },
 };
-use common::problems::{Problem, ProblemContent};
+use common::{
+    grading::{Grade, Submission},
+    problems::{FreeResponseRestriction, Problem, ProblemContent, ProblemType},
+    random,
+    reports::{NewReport, ReportReason},
+    vetting::{VettedAnswer, VettingStatus},
+};
 use log::*;
 use yew::{
     virtual_dom::{VNode, VText},
@@ -23,23 +30,88 @@ pub enum ProblemStatus {
     Failed(String),
 }
 
+#[derive(Debug, Clone)]
 pub enum LatexablePart {
     Text(String),
     Latex(String),
 }
 
+impl LatexablePart {
+    /// Splits text into plain and `$$`-delimited latex parts.
+    pub fn parse(text: &str) -> Vec<LatexablePart> {
+        text.split("$$")
+            .enumerate()
+            .map(|(i, part)| {
+                if i % 2 == 0 {
+                    LatexablePart::Text(part.to_string())
+                } else {
+                    LatexablePart::Latex(part.to_string())
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn reason_name(reason: ReportReason) -> &'static str {
+    match reason {
+        ReportReason::WrongAnswer => "Wrong answer",
+        ReportReason::Spam => "Spam",
+        ReportReason::Offensive => "Offensive",
+        ReportReason::Duplicate => "Duplicate",
+    }
+}
+
+fn view_latexable(parts: &[LatexablePart]) -> Html {
+    html! {
+        <>
+            {
+                for parts.iter().map(|part| match part {
+                    LatexablePart::Text(text) => html! {
+                        <span class="part">{ text }</span>
+                    },
+                    LatexablePart::Latex(text) => html! {
+                        <span class="part rendermath">{ text }</span>
+                    }
+                })
+            }
+        </>
+    }
+}
+
 pub enum ProblemMsg {
     StatusUpdate(ProblemStatus),
     ToggleRec,
     RecSuccess(i32),
     RecFailure(String),
     ToggleViewState,
+    UpdateSubmission(Submission),
+    UpdatePartSubmission(usize, Submission),
+    GradeSuccess(Grade),
+    GradeFailure(String),
+    ToggleHidden,
+    HiddenChanged(bool),
+    UpdateTags(String),
+    SaveTags,
+    TagsSaved(Vec<String>),
+    ModerationFailure(String),
+    ToggleReport,
+    UpdateReportReason(ReportReason),
+    UpdateReportComment(String),
+    SendReport,
+    ReportSent,
+    ReportFailure(String),
+    VettedAnswersLoaded(Vec<VettedAnswer>),
+    VettedAnswersFailure(String),
 }
 
 #[derive(Debug, Clone, Properties)]
 pub struct ProblemProps {
     pub problemid: i32,
+    pub user_id: i32,
     pub recommended: bool,
+    /// Shows the controls for hiding the problem and editing its tags.
+    #[prop_or_default]
+    pub can_moderate: bool,
 }
 
 pub struct ProblemComponent {
@@ -47,11 +119,26 @@ pub struct ProblemComponent {
     fetch_service: FetchService,
     problem_ft: Option<FetchTask>,
     rec_ft: Option<FetchTask>,
+    submit_ft: Option<FetchTask>,
+    moderation_ft: Option<FetchTask>,
+    report_ft: Option<FetchTask>,
+    vetting_ft: Option<FetchTask>,
     props: ProblemProps,
     problem: ProblemStatus,
     problem_prompt: Vec<LatexablePart>,
     problem_explanation: Vec<LatexablePart>,
     viewing_solution: bool,
+    submission: Option<Submission>,
+    grade: Option<Grade>,
+    /// Comma-separated, as typed into the moderation controls.
+    tags_input: String,
+    /// Whether the report form is open.
+    reporting: bool,
+    report: NewReport,
+    /// Shown in place of the report form once it's been sent or has failed.
+    report_message: Option<String>,
+    /// How the owner vetted the user's wrong answers to a free-response problem.
+    vetted_answers: Vec<VettedAnswer>,
 }
 
 impl ProblemComponent {
@@ -63,6 +150,118 @@ impl ProblemComponent {
         self.link.callback(move |_| ProblemMsg::ToggleViewState)
     }
 
+    fn update_submission(&self) -> Callback<Submission> {
+        self.link.callback(ProblemMsg::UpdateSubmission)
+    }
+
+    /// Renders the answer area for `content`. Only cloze problems
+    /// use `prompt`, since their blanks are part of it.
+    fn view_content(
+        &self,
+        content: &ProblemContent,
+        prompt: &[LatexablePart],
+        grade: Option<Grade>,
+        onsubmission: Callback<Submission>,
+    ) -> Html {
+        match content {
+            ProblemContent::FreeResponse { restrictions, solution } => {
+                html! {
+                    <FreeRespComponent restrictions={ restrictions } solution={ solution } onsubmission=onsubmission />
+                }
+            }
+            ProblemContent::MultipleChoice { options, solution } => {
+                html! {
+                    <MultChoiceComponent options={ options } solution={ solution } onsubmission=onsubmission />
+                }
+            }
+            ProblemContent::Checklist { options, solution, .. } => {
+                html! {
+                    <ChecklistComponent options={ options } solution={ solution } onsubmission=onsubmission grade=grade />
+                }
+            }
+            ProblemContent::Ordering { items, .. } => {
+                html! {
+                    <OrderingComponent problemid=self.props.problemid user_id=self.props.user_id items={ items } onsubmission=onsubmission grade=grade />
+                }
+            }
+            ProblemContent::Matching { left, right, .. } => {
+                html! {
+                    <MatchingComponent problemid=self.props.problemid user_id=self.props.user_id left={ left } right={ right } onsubmission=onsubmission grade=grade />
+                }
+            }
+            ProblemContent::Template { .. } => {
+                // Answers are numbers, possibly written as expressions like 3/4
+                html! {
+                    <FreeRespComponent restrictions={ vec![FreeResponseRestriction::Expression] } solution={ vec![] } onsubmission=onsubmission />
+                }
+            }
+            ProblemContent::Cloze { blanks } => {
+                html! {
+                    <ClozeComponent prompt={ prompt.to_vec() } blanks={ blanks } onsubmission=onsubmission grade=grade />
+                }
+            }
+            ProblemContent::Translation {
+                languages,
+                examples,
+                items,
+                ..
+            } => {
+                html! {
+                    <TranslationComponent languages={ languages } examples={ examples } items={ items } onsubmission=onsubmission grade=grade />
+                }
+            }
+            ProblemContent::Grid(puzzle) => {
+                html! {
+                    <GridComponent puzzle={ puzzle } onsubmission=onsubmission grade=grade />
+                }
+            }
+            ProblemContent::MultiPart { parts } => {
+                let seed = random::user_problem_seed(self.props.user_id, self.props.problemid);
+                html! {
+                    <div class="multipart">
+                        {
+                            for parts.iter().enumerate().map(|(i, part)| {
+                                let part_grade = grade.as_ref().and_then(|grade| grade.parts.get(i).cloned().flatten());
+                                let part_prompt = part
+                                    .content
+                                    .instantiate_text(&part.prompt, seed)
+                                    .map(|prompt| LatexablePart::parse(&prompt));
+                                html! {
+                                    <div class="subpart">
+                                        <div class="subpartprompt">
+                                            <span class="partlabel">{ format!("({})", (b'a' + i as u8) as char) }</span>
+                                            {
+                                                match &part_prompt {
+                                                    Ok(_) if part.content.get_type() == ProblemType::Cloze => html! {},
+                                                    Ok(prompt) => view_latexable(prompt),
+                                                    Err(error) => html! { <div class="errorbox">{ error }</div> },
+                                                }
+                                            }
+                                        </div>
+                                        {
+                                            match (&part.content, &part_prompt) {
+                                                (ProblemContent::MultiPart { .. }, _) => html! {
+                                                    <div class="errorbox">{ "Nested parts are not supported" }</div>
+                                                },
+                                                (_, Ok(prompt)) => self.view_content(
+                                                    &part.content,
+                                                    prompt,
+                                                    part_grade,
+                                                    self.link.callback(move |submission| ProblemMsg::UpdatePartSubmission(i, submission)),
+                                                ),
+                                                (_, Err(_)) => html! {},
+                                            }
+                                        }
+                                    </div>
+                                }
+                            })
+                        }
+                    </div>
+                }
+            }
+        }
+    }
+
     fn send_problem_request(&mut self) -> FetchTask {
         let callback = self.link.callback(
             move |response: Response<Json<Result<Problem, anyhow::Error>>>| {
@@ -82,7 +281,9 @@ impl ProblemComponent {
         let request = Request::get(format!("{}/problems/{}/", API_URL, self.props.problemid))
             .body(Nothing)
             .unwrap();
-        self.fetch_service.fetch(request, callback).unwrap()
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
     }
 
     fn send_rec_request(&mut self) -> FetchTask {
@@ -107,7 +308,195 @@ impl ProblemComponent {
         ))
         .body(Nothing)
         .unwrap();
-        self.fetch_service.fetch(request, callback).unwrap()
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
+    }
+
+    fn send_hide_request(&mut self, hidden: bool) -> FetchTask {
+        let callback = self.link.callback(move |response: Response<Nothing>| {
+            let (meta, _) = response.into_parts();
+            if meta.status.is_success() {
+                ProblemMsg::HiddenChanged(hidden)
+            } else {
+                ProblemMsg::ModerationFailure(format!("{}", meta.status))
+            }
+        });
+        let request = Request::post(format!(
+            "{}/problems/{}/hide/{}",
+            API_URL, self.props.problemid, hidden
+        ))
+        .body(Nothing)
+        .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
+    }
+
+    fn send_tags_request(&mut self, tags: Vec<String>) -> FetchTask {
+        let request = Request::put(format!("{}/problems/{}/tags", API_URL, self.props.problemid))
+            .header("Content-Type", "application/json")
+            .body(Json(&tags))
+            .unwrap();
+        let callback = self.link.callback(move |response: Response<Nothing>| {
+            let (meta, _) = response.into_parts();
+            if meta.status.is_success() {
+                ProblemMsg::TagsSaved(tags.clone())
+            } else {
+                ProblemMsg::ModerationFailure(format!("{}", meta.status))
+            }
+        });
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
+    }
+
+    fn view_moderation(&self, problem: &Problem) -> Html {
+        if !self.props.can_moderate {
+            return html! {};
+        }
+        let hidden = problem.hidden;
+        html! {
+            <div class="moderation">
+                <div class="hide" onclick=self.link.callback(|_| ProblemMsg::ToggleHidden)>
+                    { if hidden { "Unhide" } else { "Hide" } }
+                </div>
+                <input type="text" class="tagsinput" value=&self.tags_input oninput=self.link.callback(|event: InputData| ProblemMsg::UpdateTags(event.value)) />
+                <div class="savetags" onclick=self.link.callback(|_| ProblemMsg::SaveTags)>
+                    { "Save tags" }
+                </div>
+            </div>
+        }
+    }
+
+    fn send_report_request(&mut self) -> FetchTask {
+        let request = Request::post(format!("{}/problems/{}/report", API_URL, self.props.problemid))
+            .header("Content-Type", "application/json")
+            .body(Json(&self.report))
+            .unwrap();
+        let callback = self.link.callback(move |response: Response<Result<String, anyhow::Error>>| {
+            let (meta, body) = response.into_parts();
+            if meta.status.is_success() {
+                ProblemMsg::ReportSent
+            } else if meta.status.as_u16() == 429 {
+                ProblemMsg::ReportFailure("You've sent a lot of reports, please try again later".to_string())
+            } else {
+                ProblemMsg::ReportFailure(body.unwrap_or_else(|_| format!("{}", meta.status)))
+            }
+        });
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
+    }
+
+    fn view_report(&self) -> Html {
+        if let Some(message) = &self.report_message {
+            return html! { <div class="reportmessage">{ message }</div> };
+        }
+        if !self.reporting {
+            return html! {
+                <div class="reporttoggle" onclick=self.link.callback(|_| ProblemMsg::ToggleReport)>
+                    { "Report" }
+                </div>
+            };
+        }
+        let select_reason = self.link.callback(|data: ChangeData| match data {
+            ChangeData::Select(select) => match select.value().parse() {
+                Ok(reason) => ProblemMsg::UpdateReportReason(reason),
+                Err(error) => ProblemMsg::ReportFailure(error),
+            },
+            _ => ProblemMsg::ReportFailure("Expected a reason to be selected".to_string()),
+        });
+        html! {
+            <div class="report">
+                <select onchange=select_reason>
+                    {
+                        for ReportReason::ALL.iter().map(|reason| html! {
+                            <option value=reason.to_string() selected=*reason == self.report.reason>
+                                { reason_name(*reason) }
+                            </option>
+                        })
+                    }
+                </select>
+                <input type="text" class="reportcomment" placeholder="What's wrong?" value=&self.report.comment oninput=self.link.callback(|event: InputData| ProblemMsg::UpdateReportComment(event.value)) />
+                <div class="sendreport" onclick=self.link.callback(|_| ProblemMsg::SendReport)>
+                    { "Send report" }
+                </div>
+                <div class="cancelreport" onclick=self.link.callback(|_| ProblemMsg::ToggleReport)>
+                    { "Cancel" }
+                </div>
+            </div>
+        }
+    }
+
+    fn send_vetting_request(&mut self) -> FetchTask {
+        let callback = self.link.callback(
+            move |response: Response<Json<Result<Vec<VettedAnswer>, anyhow::Error>>>| {
+                let (meta, Json(answers)) = response.into_parts();
+                match answers {
+                    Ok(answers) if meta.status.is_success() => ProblemMsg::VettedAnswersLoaded(answers),
+                    _ => ProblemMsg::VettedAnswersFailure(format!("{}", meta.status)),
+                }
+            },
+        );
+        let request = Request::get(format!("{}/problems/{}/vetting", API_URL, self.props.problemid))
+            .body(Nothing)
+            .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
+    }
+
+    fn view_vetted_answers(&self) -> Html {
+        html! {
+            <div class="vettedanswers">
+                {
+                    for self.vetted_answers.iter().map(|answer| {
+                        let (class, verdict) = match answer.status {
+                            VettingStatus::Pending => ("vettedanswer pending", "is waiting for the owner to take a look".to_string()),
+                            VettingStatus::Accepted => ("vettedanswer accepted", "was accepted by the owner".to_string()),
+                            VettingStatus::Rejected => (
+                                "vettedanswer rejected",
+                                format!("was rejected by the owner: {}", answer.note.as_deref().unwrap_or_default()),
+                            ),
+                        };
+                        html! {
+                            <div class=class>
+                                { format!("Your answer \"{}\" {}", answer.response, verdict) }
+                            </div>
+                        }
+                    })
+                }
+            </div>
+        }
+    }
+
+    fn send_submit_request(&mut self, submission: &Submission) -> FetchTask {
+        let callback = self.link.callback(
+            move |response: Response<Json<Result<Grade, anyhow::Error>>>| {
+                let (meta, Json(grade)) = response.into_parts();
+                match grade {
+                    Ok(grade) => {
+                        if meta.status.is_success() {
+                            ProblemMsg::GradeSuccess(grade)
+                        } else {
+                            ProblemMsg::GradeFailure(format!("{}", meta.status))
+                        }
+                    }
+                    Err(error) => ProblemMsg::GradeFailure(format!("ERROR: {}", error)),
+                }
+            },
+        );
+        let request = Request::post(format!(
+            "{}/problems/{}/submit",
+            API_URL, self.props.problemid
+        ))
+        .header("Content-Type", "application/json")
+        .body(Json(submission))
+        .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
     }
 }
 
@@ -121,11 +510,25 @@ impl Component for ProblemComponent {
             fetch_service: FetchService::new(),
             problem_ft: None,
             rec_ft: None,
+            submit_ft: None,
+            moderation_ft: None,
+            report_ft: None,
             problem: ProblemStatus::Loading,
             problem_prompt: vec![],
             problem_explanation: vec![],
             props,
             viewing_solution: false,
+            submission: None,
+            grade: None,
+            tags_input: String::new(),
+            reporting: false,
+            report: NewReport {
+                reason: ReportReason::WrongAnswer,
+                comment: String::new(),
+            },
+            report_message: None,
+            vetting_ft: None,
+            vetted_answers: vec![],
         };
         component.problem_ft = Some(component.send_problem_request());
         component
@@ -136,25 +539,26 @@ impl Component for ProblemComponent {
             ProblemMsg::StatusUpdate(status) => {
                 match &status {
                     ProblemStatus::Loaded(problem) => {
-                        self.problem_prompt = vec![];
-                        for (i, part) in problem.prompt.split("$$").enumerate() {
-                            self.problem_prompt.push(
-                                if i % 2 == 0 {
-                                    LatexablePart::Text(part.to_string())
-                                } else {
-                                    LatexablePart::Latex(part.to_string())
-                                }
-                            );
+                        self.tags_input = problem.tags.join(", ");
+                        if problem.content.get_type() == ProblemType::FreeResponse {
+                            self.vetting_ft = Some(self.send_vetting_request());
                         }
-                        self.problem_explanation = vec![];
-                        for (i, part) in problem.explanation.split("$$").enumerate() {
-                            self.problem_explanation.push(
-                                if i % 2 == 0 {
-                                    LatexablePart::Text(part.to_string())
-                                } else {
-                                    LatexablePart::Latex(part.to_string())
-                                }
-                            );
+                        let seed = random::user_problem_seed(self.props.user_id, problem.id);
+                        let texts = problem
+                            .content
+                            .instantiate_text(&problem.prompt, seed)
+                            .and_then(|prompt| {
+                                Ok((prompt, problem.content.instantiate_text(&problem.explanation, seed)?))
+                            });
+                        match texts {
+                            Ok((prompt, explanation)) => {
+                                self.problem_prompt = LatexablePart::parse(&prompt);
+                                self.problem_explanation = LatexablePart::parse(&explanation);
+                            }
+                            Err(error) => {
+                                self.problem = ProblemStatus::Failed(error);
+                                return true;
+                            }
                         }
                     },
                     _ => (),
@@ -184,9 +588,127 @@ impl Component for ProblemComponent {
                 false
             }
             ProblemMsg::ToggleViewState => {
+                if !self.viewing_solution {
+                    if let Some(submission) = self.submission.clone() {
+                        self.submit_ft = Some(self.send_submit_request(&submission));
+                    }
+                }
                 self.viewing_solution = !self.viewing_solution;
                 true
             }
+            ProblemMsg::UpdateSubmission(submission) => {
+                self.submission = Some(submission);
+                false
+            }
+            ProblemMsg::UpdatePartSubmission(idx, submission) => {
+                let part_count = match &self.problem {
+                    ProblemStatus::Loaded(Problem {
+                        content: ProblemContent::MultiPart { parts },
+                        ..
+                    }) => parts.len(),
+                    _ => return false,
+                };
+                match &mut self.submission {
+                    Some(Submission::MultiPart(parts)) => parts[idx] = Some(submission),
+                    _ => {
+                        let mut parts = vec![None; part_count];
+                        parts[idx] = Some(submission);
+                        self.submission = Some(Submission::MultiPart(parts));
+                    }
+                }
+                false
+            }
+            ProblemMsg::GradeSuccess(grade) => {
+                let is_free_response = match &self.problem {
+                    ProblemStatus::Loaded(problem) => problem.content.get_type() == ProblemType::FreeResponse,
+                    _ => false,
+                };
+                // Wrong free responses are queued for the owner, so show the new one
+                if is_free_response && !grade.is_correct() {
+                    self.vetting_ft = Some(self.send_vetting_request());
+                }
+                self.grade = Some(grade);
+                true
+            }
+            ProblemMsg::GradeFailure(error_message) => {
+                info!("Error when grading submission: {}", error_message);
+                false
+            }
+            ProblemMsg::ToggleHidden => {
+                if let ProblemStatus::Loaded(problem) = &self.problem {
+                    let hidden = !problem.hidden;
+                    self.moderation_ft = Some(self.send_hide_request(hidden));
+                }
+                false
+            }
+            ProblemMsg::HiddenChanged(hidden) => {
+                if let ProblemStatus::Loaded(problem) = &mut self.problem {
+                    problem.hidden = hidden;
+                }
+                true
+            }
+            ProblemMsg::UpdateTags(tags) => {
+                self.tags_input = tags;
+                false
+            }
+            ProblemMsg::SaveTags => {
+                let tags = self
+                    .tags_input
+                    .split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect();
+                self.moderation_ft = Some(self.send_tags_request(tags));
+                false
+            }
+            ProblemMsg::TagsSaved(tags) => {
+                if let ProblemStatus::Loaded(problem) = &mut self.problem {
+                    problem.tags = tags;
+                }
+                true
+            }
+            ProblemMsg::ModerationFailure(error_message) => {
+                info!("Error when moderating problem: {}", error_message);
+                false
+            }
+            ProblemMsg::ToggleReport => {
+                self.reporting = !self.reporting;
+                true
+            }
+            ProblemMsg::UpdateReportReason(reason) => {
+                self.report.reason = reason;
+                false
+            }
+            ProblemMsg::UpdateReportComment(comment) => {
+                self.report.comment = comment;
+                false
+            }
+            ProblemMsg::SendReport => {
+                if let Err(error) = self.report.validate() {
+                    self.report_message = Some(error);
+                    return true;
+                }
+                self.report_ft = Some(self.send_report_request());
+                false
+            }
+            ProblemMsg::ReportSent => {
+                self.reporting = false;
+                self.report_message = Some("Thanks, a moderator will take a look".to_string());
+                true
+            }
+            ProblemMsg::ReportFailure(error_message) => {
+                info!("Error when reporting problem: {}", error_message);
+                self.report_message = Some(error_message);
+                true
+            }
+            ProblemMsg::VettedAnswersLoaded(answers) => {
+                self.vetted_answers = answers;
+                true
+            }
+            ProblemMsg::VettedAnswersFailure(error_message) => {
+                info!("Error when loading vetted answers: {}", error_message);
+                false
+            }
         }
     }
 
@@ -197,24 +719,29 @@ impl Component for ProblemComponent {
                     <div class="headerwrapper">
                         <div class="header">
                             <div class="topic">
-                                { serde_json::to_string(&problem.topic).unwrap().replace(r#"""#, "").replace("Trivia", "Linguistics") }
+                                { problem.topic.display_name() }
                             </div>
                             <div class="tags">
                                 { for problem.tags.iter().map(|tag| html! { <div class="tag">{tag}</div> }) }
                             </div>
+                            {
+                                if problem.hidden {
+                                    html! { <div class="hiddenbadge">{ "Hidden" }</div> }
+                                } else {
+                                    html! {}
+                                }
+                            }
                         </div>
                     </div>
 
                     <div class="prompt">
                         {
-                            for self.problem_prompt.iter().map(|part| match part {
-                                LatexablePart::Text(text) => html! {
-                                    <span class="part">{ text }</span>
-                                },
-                                LatexablePart::Latex(text) => html! {
-                                    <span class="part rendermath">{ text }</span>
-                                }
-                            })
+                            // Cloze prompts are shown by the answer area, blanks and all
+                            if problem.content.get_type() == ProblemType::Cloze && !self.viewing_solution {
+                                html! {}
+                            } else {
+                                view_latexable(&self.problem_prompt)
+                            }
                         }
                     </div>
 
@@ -224,35 +751,22 @@ impl Component for ProblemComponent {
                                 html! {
                                     <div class="solution">
                                         {
-                                            for self.problem_explanation.iter().map(|part| match part {
-                                                LatexablePart::Text(text) => html! {
-                                                    <span class="part">{ text }</span>
-                                                },
-                                                LatexablePart::Latex(text) => html! {
-                                                    <span class="part rendermath">{ text }</span>
+                                            if let Some(grade) = &self.grade {
+                                                html! {
+                                                    <div class={ if grade.is_correct() { "grade correct" } else { "grade incorrect" } }>
+                                                        { format!("Score: {} / {}", grade.score, grade.max) }
+                                                    </div>
                                                 }
-                                            })
+                                            } else {
+                                                html! {}
+                                            }
                                         }
+                                        { self.view_vetted_answers() }
+                                        { view_latexable(&self.problem_explanation) }
                                     </div>
                                 }
                             } else {
-                                match &problem.content {
-                                    ProblemContent::FreeResponse { restrictions, solution } => {
-                                        html! {
-                                            <FreeRespComponent restrictions={ restrictions } solution={ solution } />
-                                        }
-                                    }
-                                    ProblemContent::MultipleChoice { options, solution } => {
-                                        html! {
-                                            <MultChoiceComponent options={ options } solution={ solution } />
-                                        }
-                                    }
-                                    ProblemContent::Checklist { options, solution } => {
-                                        html! {
-                                            <ChecklistComponent options={ options } solution={ solution } />
-                                        }
-                                    }
-                                }
+                                self.view_content(&problem.content, &self.problem_prompt, self.grade.clone(), self.update_submission())
                             }
                         }
                     </div>
@@ -288,6 +802,8 @@ impl Component for ProblemComponent {
                                 }
                             }
                         }
+                        { self.view_report() }
+                        { self.view_moderation(problem) }
                     </div>
                 </div>
             },
@@ -0,0 +1,197 @@
+use crate::app::{fetch_options, API_URL};
+use common::vetting::{AnswerCandidate, Rejection};
+use log::*;
+use std::collections::HashMap;
+use yew::{
+    format::{Json, Nothing},
+    prelude::*,
+    services::{
+        fetch::{FetchTask, Request, Response},
+        FetchService,
+    },
+};
+
+pub enum VettingMsg {
+    Loaded(Vec<AnswerCandidate>),
+    Accept(i32),
+    SetNote(i32, String),
+    Reject(i32),
+    /// The candidate was vetted, so it leaves the queue.
+    Vetted(i32),
+    Failure(String),
+}
+
+/// Wrong answers to the signed in user's free-response problems, where they
+/// accept them as solutions or reject them with a note.
+pub struct VettingComponent {
+    link: ComponentLink<Self>,
+    fetch_service: FetchService,
+    candidates_ft: Option<FetchTask>,
+    action_ft: Option<FetchTask>,
+    candidates: Vec<AnswerCandidate>,
+    /// The rejection note typed for each candidate, by id.
+    notes: HashMap<i32, String>,
+    error: Option<String>,
+}
+
+impl VettingComponent {
+    fn send_candidates_request(&mut self) -> FetchTask {
+        let callback = self.link.callback(
+            move |response: Response<Json<Result<Vec<AnswerCandidate>, anyhow::Error>>>| {
+                let (meta, Json(candidates)) = response.into_parts();
+                match candidates {
+                    Ok(candidates) if meta.status.is_success() => VettingMsg::Loaded(candidates),
+                    _ => VettingMsg::Failure(format!("{}", meta.status)),
+                }
+            },
+        );
+        let request = Request::get(format!("{}/vetting/", API_URL))
+            .body(Nothing)
+            .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), callback)
+            .unwrap()
+    }
+
+    fn vetted_callback(&self, id: i32) -> Callback<Response<Result<String, anyhow::Error>>> {
+        self.link
+            .callback(move |response: Response<Result<String, anyhow::Error>>| {
+                let (meta, body) = response.into_parts();
+                if meta.status.is_success() {
+                    VettingMsg::Vetted(id)
+                } else {
+                    // Refusals come with a message saying why
+                    VettingMsg::Failure(body.unwrap_or_else(|_| format!("{}", meta.status)))
+                }
+            })
+    }
+
+    fn send_accept_request(&mut self, id: i32) -> FetchTask {
+        let request = Request::post(format!("{}/vetting/{}/accept", API_URL, id))
+            .body(Nothing)
+            .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), self.vetted_callback(id))
+            .unwrap()
+    }
+
+    fn send_reject_request(&mut self, id: i32, rejection: &Rejection) -> FetchTask {
+        let request = Request::post(format!("{}/vetting/{}/reject", API_URL, id))
+            .header("Content-Type", "application/json")
+            .body(Json(rejection))
+            .unwrap();
+        self.fetch_service
+            .fetch_with_options(request, fetch_options(), self.vetted_callback(id))
+            .unwrap()
+    }
+
+    fn view_candidate(&self, candidate: &AnswerCandidate) -> Html {
+        let id = candidate.id;
+        let note = self.notes.get(&id).cloned().unwrap_or_default();
+        let given_by = if candidate.submissions == 1 {
+            "Given once".to_string()
+        } else {
+            format!("Given {} times", candidate.submissions)
+        };
+        html! {
+            <div class="candidate">
+                <div class="candidateheader">
+                    <span class="candidateproblem">{ format!("Problem #{}", candidate.problem_id) }</span>
+                    <span class="candidatecount">{ given_by }</span>
+                </div>
+                <div class="candidateanswer">{ &candidate.example }</div>
+                <div class="candidateactions">
+                    <div class="accept" onclick=self.link.callback(move |_| VettingMsg::Accept(id))>
+                        { "Accept" }
+                    </div>
+                    <input type="text" class="rejectionnote" placeholder="Why is it wrong?" value=note oninput=self.link.callback(move |event: InputData| VettingMsg::SetNote(id, event.value)) />
+                    <div class="reject" onclick=self.link.callback(move |_| VettingMsg::Reject(id))>
+                        { "Reject" }
+                    </div>
+                </div>
+            </div>
+        }
+    }
+}
+
+impl Component for VettingComponent {
+    type Message = VettingMsg;
+    type Properties = ();
+
+    fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let mut component = Self {
+            link,
+            fetch_service: FetchService::new(),
+            candidates_ft: None,
+            action_ft: None,
+            candidates: vec![],
+            notes: HashMap::new(),
+            error: None,
+        };
+        component.candidates_ft = Some(component.send_candidates_request());
+        component
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            VettingMsg::Loaded(candidates) => {
+                self.candidates = candidates;
+                self.error = None;
+                true
+            }
+            VettingMsg::Accept(id) => {
+                self.action_ft = Some(self.send_accept_request(id));
+                false
+            }
+            VettingMsg::SetNote(id, note) => {
+                self.notes.insert(id, note);
+                false
+            }
+            VettingMsg::Reject(id) => {
+                let rejection = Rejection {
+                    note: self.notes.get(&id).cloned().unwrap_or_default(),
+                };
+                if let Err(error) = rejection.validate() {
+                    self.error = Some(error);
+                    return true;
+                }
+                self.action_ft = Some(self.send_reject_request(id, &rejection));
+                false
+            }
+            VettingMsg::Vetted(id) => {
+                self.candidates.retain(|candidate| candidate.id != id);
+                self.notes.remove(&id);
+                self.error = None;
+                true
+            }
+            VettingMsg::Failure(error) => {
+                info!("Error when vetting answers: {}", error);
+                self.error = Some(error);
+                true
+            }
+        }
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div class="vettingwrapper">
+                <div class="vettingqueue">
+                    {
+                        if let Some(error) = &self.error {
+                            html! { <div class="errorbox">{ error }</div> }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    {
+                        if self.candidates.is_empty() {
+                            html! { <div class="nocandidates">{ "No wrong answers to vet" }</div> }
+                        } else {
+                            html! { for self.candidates.iter().map(|candidate| self.view_candidate(candidate)) }
+                        }
+                    }
+                </div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// A complex number in rectangular form.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    /// Checks if both the real and imaginary parts of `self`
+    /// are within `precision` of the parts of `other`.
+    pub fn approx_eq(&self, other: &Complex, precision: f64) -> bool {
+        (self.re - other.re).abs() < precision && (self.im - other.im).abs() < precision
+    }
+
+    /// Parses a complex number written as a sum of real and imaginary terms,
+    /// such as `3+4i`, `-2i`, `i`, or `1.5e3 - i`.
+    /// returns a user-displayable error message if the input is malformed.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input: String = input.chars().filter(|c| !c.is_whitespace()).collect();
+        if input.is_empty() {
+            return Err("Input must be a complex number".to_string());
+        }
+
+        let mut result = Complex::default();
+        for term in split_terms(&input) {
+            let (coefficient, imaginary) = match term.strip_suffix('i') {
+                Some(coefficient) => (coefficient.strip_suffix('*').unwrap_or(coefficient), true),
+                None => (term, false),
+            };
+            let value = match coefficient {
+                "" | "+" if imaginary => 1.0,
+                "-" if imaginary => -1.0,
+                _ => match coefficient.parse::<f64>() {
+                    Ok(value) if value.is_finite() => value,
+                    _ => return Err(format!("\"{}\" is not a valid complex term", term)),
+                },
+            };
+            if imaginary {
+                result.im += value;
+            } else {
+                result.re += value;
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Splits a whitespace-free expression into signed terms, leaving
+/// the signs of scientific notation exponents (e.g. `1e-3`) attached.
+fn split_terms(input: &str) -> Vec<&str> {
+    let bytes = input.as_bytes();
+    let mut terms = vec![];
+    let mut start = 0;
+    for i in 1..bytes.len() {
+        let is_sign = bytes[i] == b'+' || bytes[i] == b'-';
+        let is_exponent = (bytes[i - 1] == b'e' || bytes[i - 1] == b'E')
+            && i >= 2
+            && (bytes[i - 2].is_ascii_digit() || bytes[i - 2] == b'.');
+        if is_sign && !is_exponent {
+            terms.push(&input[start..i]);
+            start = i;
+        }
+    }
+    terms.push(&input[start..]);
+    terms
+}
+
+impl FromStr for Complex {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Complex::parse(s)
+    }
+}
+
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.im == 0.0 {
+            write!(f, "{}", self.re)
+        } else if self.re == 0.0 {
+            write!(f, "{}i", self.im)
+        } else if self.im < 0.0 {
+            write!(f, "{} - {}i", self.re, -self.im)
+        } else {
+            write!(f, "{} + {}i", self.re, self.im)
+        }
+    }
+}
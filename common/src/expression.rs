@@ -0,0 +1,496 @@
+use crate::random::SeededRng;
+use std::{
+    collections::{BTreeSet, HashMap},
+    f64::consts,
+};
+
+/// The number of points two expressions are compared at.
+const SAMPLE_COUNT: usize = 24;
+/// The number of sample points at which the reference expression
+/// must be defined for a comparison to be meaningful.
+const MIN_VALID_SAMPLES: usize = 8;
+/// How deeply parentheses, functions, exponents and signs may nest, so a
+/// malicious response can't overflow the stack while being parsed.
+const MAX_DEPTH: usize = 64;
+/// Long flat chains like `1+1+1+...` nest just as deeply once parsed,
+/// and the parsed tree is walked recursively too.
+const MAX_TOKENS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Constant {
+    Pi,
+    E,
+    Tau,
+}
+
+impl Constant {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "pi" | "π" => Some(Constant::Pi),
+            "e" => Some(Constant::E),
+            "tau" | "τ" => Some(Constant::Tau),
+            _ => None,
+        }
+    }
+
+    pub fn value(self) -> f64 {
+        match self {
+            Constant::Pi => consts::PI,
+            Constant::E => consts::E,
+            Constant::Tau => 2.0 * consts::PI,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Sqrt,
+    Cbrt,
+    Abs,
+    Exp,
+    Ln,
+    Log,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Floor,
+    Ceil,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sqrt" | "√" => Function::Sqrt,
+            "cbrt" => Function::Cbrt,
+            "abs" => Function::Abs,
+            "exp" => Function::Exp,
+            "ln" => Function::Ln,
+            "log" => Function::Log,
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "asin" | "arcsin" => Function::Asin,
+            "acos" | "arccos" => Function::Acos,
+            "atan" | "arctan" => Function::Atan,
+            "sinh" => Function::Sinh,
+            "cosh" => Function::Cosh,
+            "tanh" => Function::Tanh,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            _ => return None,
+        })
+    }
+
+    pub fn apply(self, x: f64) -> f64 {
+        match self {
+            Function::Sqrt => x.sqrt(),
+            Function::Cbrt => x.cbrt(),
+            Function::Abs => x.abs(),
+            Function::Exp => x.exp(),
+            Function::Ln => x.ln(),
+            Function::Log => x.log10(),
+            Function::Sin => x.sin(),
+            Function::Cos => x.cos(),
+            Function::Tan => x.tan(),
+            Function::Asin => x.asin(),
+            Function::Acos => x.acos(),
+            Function::Atan => x.atan(),
+            Function::Sinh => x.sinh(),
+            Function::Cosh => x.cosh(),
+            Function::Tanh => x.tanh(),
+            Function::Floor => x.floor(),
+            Function::Ceil => x.ceil(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// A parsed mathematical expression over real numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Constant(Constant),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+impl Expr {
+    /// Parses an expression such as `2*sqrt(3)`, `pi/4` or `(x+1)^2`.
+    /// Multiplication may be implicit (`2x`, `3(x+1)`) and `**` is accepted for `^`.
+    /// returns a user-displayable error message if the input is malformed.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let tokens = tokenize(input)?;
+        if tokens.len() > MAX_TOKENS {
+            return Err(format!(
+                "Expression is too long, it can have at most {} numbers, names and symbols",
+                MAX_TOKENS
+            ));
+        }
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let expr = parser.expression()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(token) => Err(format!("Unexpected {}", token.describe())),
+        }
+    }
+
+    /// Evaluates the expression with the given variable values.
+    /// Errors if the expression uses a variable that has no value.
+    pub fn eval(&self, variables: &HashMap<String, f64>) -> Result<f64, String> {
+        Ok(match self {
+            Expr::Number(num) => *num,
+            Expr::Constant(constant) => constant.value(),
+            Expr::Variable(name) => *variables
+                .get(name)
+                .ok_or_else(|| format!("Unknown variable \"{}\"", name))?,
+            Expr::Negate(inner) => -inner.eval(variables)?,
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = lhs.eval(variables)?;
+                let rhs = rhs.eval(variables)?;
+                match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Sub => lhs - rhs,
+                    BinaryOp::Mul => lhs * rhs,
+                    BinaryOp::Div => lhs / rhs,
+                    BinaryOp::Pow => lhs.powf(rhs),
+                }
+            }
+            Expr::Call(function, arg) => function.apply(arg.eval(variables)?),
+        })
+    }
+
+    /// Returns the names of all variables used in the expression.
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        self.collect_variables(&mut names);
+        names
+    }
+
+    fn collect_variables(&self, names: &mut BTreeSet<String>) {
+        match self {
+            Expr::Variable(name) => {
+                names.insert(name.clone());
+            }
+            Expr::Negate(inner) | Expr::Call(_, inner) => inner.collect_variables(names),
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.collect_variables(names);
+                rhs.collect_variables(names);
+            }
+            Expr::Number(_) | Expr::Constant(_) => (),
+        }
+    }
+
+    /// The number of nodes in the expression tree,
+    /// used as a rough measure of how simplified an expression is.
+    pub fn size(&self) -> usize {
+        match self {
+            Expr::Number(_) | Expr::Constant(_) | Expr::Variable(_) => 1,
+            Expr::Negate(inner) | Expr::Call(_, inner) => 1 + inner.size(),
+            Expr::Binary(_, lhs, rhs) => 1 + lhs.size() + rhs.size(),
+        }
+    }
+
+    /// Checks whether two expressions agree (within a relative `precision`)
+    /// at a fixed, seeded set of sample points for the given variables.
+    /// Because the sample points are deterministic, the server and
+    /// the client always come to the same verdict.
+    pub fn equivalent(&self, other: &Expr, variables: &[String], precision: f64) -> bool {
+        let allowed: BTreeSet<&String> = variables.iter().collect();
+        if other.variables().iter().any(|name| !allowed.contains(name)) {
+            return false;
+        }
+
+        let mut rng = SeededRng::new(0x5EED);
+        let mut values = HashMap::new();
+        let mut valid_samples = 0;
+        let samples = if variables.is_empty() { 1 } else { SAMPLE_COUNT };
+        for _ in 0..samples {
+            for name in variables {
+                values.insert(name.clone(), rng.range_f64(-4.0, 4.0));
+            }
+            let expected = match self.eval(&values) {
+                Ok(expected) if expected.is_finite() => expected,
+                _ => continue,
+            };
+            let actual = match other.eval(&values) {
+                Ok(actual) => actual,
+                Err(_) => return false,
+            };
+            // NaN compares false, so an undefined response fails here too
+            let close = (actual - expected).abs() <= precision * expected.abs().max(1.0);
+            if !close {
+                return false;
+            }
+            valid_samples += 1;
+        }
+        valid_samples >= MIN_VALID_SAMPLES.min(samples)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Number(num) => format!("number {}", num),
+            Token::Ident(name) => format!("\"{}\"", name),
+            Token::Plus => "\"+\"".to_string(),
+            Token::Minus => "\"-\"".to_string(),
+            Token::Star => "\"*\"".to_string(),
+            Token::Slash => "\"/\"".to_string(),
+            Token::Caret => "\"^\"".to_string(),
+            Token::LParen => "\"(\"".to_string(),
+            Token::RParen => "\")\"".to_string(),
+        }
+    }
+
+    fn starts_atom(&self) -> bool {
+        matches!(self, Token::Number(_) | Token::Ident(_) | Token::LParen)
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // Scientific notation, taking care not to swallow the constant `e`
+            if i + 1 < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if chars[j] == '+' || chars[j] == '-' {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let num = text
+                .parse::<f64>()
+                .map_err(|_| format!("\"{}\" is not a valid number", text))?;
+            tokens.push(Token::Number(num));
+        } else if c.is_alphabetic() || c == '√' {
+            let start = i;
+            i += 1;
+            if c != '√' {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            tokens.push(match c {
+                '+' => Token::Plus,
+                '-' | '−' => Token::Minus,
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    i += 1;
+                    Token::Caret
+                }
+                '*' | '·' | '×' => Token::Star,
+                '/' | '÷' => Token::Slash,
+                '^' => Token::Caret,
+                '(' | '[' => Token::LParen,
+                ')' | ']' => Token::RParen,
+                _ => return Err(format!("Unexpected character \"{}\"", c)),
+            });
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// How many `unary` and `atom` calls are currently being parsed.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => BinaryOp::Add,
+                Some(Token::Minus) => BinaryOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.term()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Star) => BinaryOp::Mul,
+                Some(Token::Slash) => BinaryOp::Div,
+                Some(token) if token.starts_atom() => {
+                    // Implicit multiplication, e.g. `2x` or `(x+1)(x-1)`
+                    let rhs = self.power()?;
+                    lhs = Expr::Binary(BinaryOp::Mul, Box::new(lhs), Box::new(rhs));
+                    continue;
+                }
+                _ => return Ok(lhs),
+            };
+            self.pos += 1;
+            let rhs = self.unary()?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    /// Runs `parse` one level deeper, failing if that's too deep.
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err("Expression is nested too deeply".to_string());
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        self.nested(Self::unary_inner)
+    }
+
+    fn unary_inner(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some(Token::Minus) => {
+                self.pos += 1;
+                Ok(Expr::Negate(Box::new(self.unary()?)))
+            }
+            Some(Token::Plus) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if let Some(Token::Caret) = self.peek() {
+            self.pos += 1;
+            // Right associative, so `2^3^2` is `2^(3^2)`
+            let exponent = self.unary()?;
+            Ok(Expr::Binary(BinaryOp::Pow, Box::new(base), Box::new(exponent)))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        self.nested(Self::atom_inner)
+    }
+
+    fn atom_inner(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(num)) => Ok(Expr::Number(num)),
+            Some(Token::LParen) => {
+                let inner = self.expression()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(inner),
+                    _ => Err("Missing closing parenthesis".to_string()),
+                }
+            }
+            Some(Token::Ident(name)) => {
+                if let Some(function) = Function::from_name(&name) {
+                    // Allow `sqrt 2` as well as `sqrt(2)`, keeping `sqrt(x)^2` as `(sqrt x)^2`
+                    let arg = if let Some(Token::LParen) = self.peek() {
+                        self.atom()?
+                    } else {
+                        self.power()?
+                    };
+                    Ok(Expr::Call(function, Box::new(arg)))
+                } else if let Some(constant) = Constant::from_name(&name) {
+                    Ok(Expr::Constant(constant))
+                } else {
+                    Ok(Expr::Variable(name))
+                }
+            }
+            Some(token) => Err(format!("Unexpected {}", token.describe())),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deeply_nested_input_is_rejected() {
+        let parens = format!("{}1{}", "(".repeat(3000), ")".repeat(3000));
+        assert!(Expr::parse(&parens).is_err());
+        // Short enough to get past the token limit, so only the depth limit stops them
+        let parens = format!("{}1{}", "(".repeat(200), ")".repeat(200));
+        assert!(Expr::parse(&parens).is_err());
+        assert!(Expr::parse(&format!("{}1", "-".repeat(200))).is_err());
+        assert!(Expr::parse(&format!("{}2", "sqrt ".repeat(200))).is_err());
+        assert!(Expr::parse(&format!("{}2", "2^".repeat(200))).is_err());
+        assert!(Expr::parse(&format!("{}1", "1+".repeat(3000))).is_err());
+    }
+
+    #[test]
+    fn moderate_nesting_still_parses() {
+        let parens = format!("{}x{}", "(".repeat(20), ")".repeat(20));
+        let expr = Expr::parse(&parens).unwrap();
+        assert!(expr.equivalent(&Expr::parse("x").unwrap(), &["x".to_string()], 1e-9));
+        assert!(Expr::parse("--2^-3").is_ok());
+    }
+}
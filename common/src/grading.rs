@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+
+/// A user's answer to a problem, sent to the server for grading.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Submission {
+    FreeResponse(String),
+    MultipleChoice(usize),
+    Checklist(Vec<usize>),
+    /// Item indices in the order the user arranged them.
+    Ordering(Vec<usize>),
+    /// For each left-hand item, the index of the right-hand item matched to it.
+    Matching(Vec<Option<usize>>),
+    /// One (possibly missing) submission per part.
+    MultiPart(Vec<Option<Submission>>),
+    /// One (possibly missing) answer per blank: `FreeResponse` for
+    /// typed blanks and `MultipleChoice` for dropdowns.
+    Cloze(Vec<Option<Submission>>),
+    /// The filled-in grid of a grid puzzle, row by row.
+    Grid(Vec<Vec<Option<u32>>>),
+    /// One (possibly missing) translation per item.
+    Translation(Vec<Option<String>>),
+}
+
+/// The outcome of grading a single gradable item,
+/// e.g. one option of a checklist.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ItemGrade {
+    pub correct: bool,
+    pub score: f32,
+    pub max: f32,
+}
+
+impl ItemGrade {
+    pub fn all_or_nothing(correct: bool) -> Self {
+        Self {
+            correct,
+            score: if correct { 1.0 } else { 0.0 },
+            max: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Grade {
+    pub score: f32,
+    pub max: f32,
+    pub per_item: Vec<ItemGrade>,
+    /// The full grade of each part of a multi-part problem.
+    #[serde(default)]
+    pub parts: Vec<Option<Grade>>,
+}
+
+impl Grade {
+    /// A grade for a problem with a single right-or-wrong answer.
+    pub fn single(correct: bool) -> Self {
+        Self::from_items(vec![ItemGrade::all_or_nothing(correct)])
+    }
+
+    /// Totals the scores of the given items.
+    pub fn from_items(per_item: Vec<ItemGrade>) -> Self {
+        Self {
+            score: per_item.iter().map(|item| item.score).sum(),
+            max: per_item.iter().map(|item| item.max).sum(),
+            per_item,
+            parts: vec![],
+        }
+    }
+
+    pub fn is_correct(&self) -> bool {
+        self.score >= self.max
+    }
+}
+
+/// How a checklist submission is turned into a score.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub enum ChecklistScoring {
+    /// Full credit only if exactly the right options are selected.
+    #[default]
+    AllOrNothing,
+    /// One point for every option that is correctly selected or left unselected.
+    PerOption,
+    /// One point for every correct option selected, minus `penalty`
+    /// for every wrong option selected, never going below zero.
+    PenalizeWrong { penalty: f32 },
+}
+
+impl ChecklistScoring {
+    /// Grades `selected` against `solution`. `per_item` always has one
+    /// entry per option, marking whether that option was handled correctly.
+    pub fn grade(&self, option_count: usize, solution: &[usize], selected: &[usize]) -> Grade {
+        let items: Vec<(bool, bool)> = (0..option_count)
+            .map(|i| (solution.contains(&i), selected.contains(&i)))
+            .collect();
+        let correct = |(expected, chosen): &(bool, bool)| expected == chosen;
+
+        match self {
+            ChecklistScoring::AllOrNothing => Grade {
+                score: if items.iter().all(correct) { 1.0 } else { 0.0 },
+                max: 1.0,
+                per_item: items
+                    .iter()
+                    .map(|item| ItemGrade {
+                        correct: correct(item),
+                        score: 0.0,
+                        max: 0.0,
+                    })
+                    .collect(),
+                parts: vec![],
+            },
+            ChecklistScoring::PerOption => Grade::from_items(
+                items
+                    .iter()
+                    .map(|item| ItemGrade::all_or_nothing(correct(item)))
+                    .collect(),
+            ),
+            ChecklistScoring::PenalizeWrong { penalty } => {
+                let per_item: Vec<ItemGrade> = items
+                    .iter()
+                    .map(|&(expected, chosen)| ItemGrade {
+                        correct: expected == chosen,
+                        score: match (expected, chosen) {
+                            (true, true) => 1.0,
+                            (false, true) => -penalty,
+                            _ => 0.0,
+                        },
+                        max: if expected { 1.0 } else { 0.0 },
+                    })
+                    .collect();
+                let mut grade = Grade::from_items(per_item);
+                grade.score = grade.score.max(0.0);
+                grade
+            }
+        }
+    }
+}
+
+/// Checks that `order` has each of `0..len` exactly once.
+pub fn is_permutation(order: &[usize], len: usize) -> bool {
+    let mut seen = vec![false; len];
+    order.len() == len
+        && order
+            .iter()
+            .all(|item| *item < len && !std::mem::replace(&mut seen[*item], true))
+}
+
+/// Grades an ordering, with one item per position marking whether the
+/// right item ended up there. With `partial_credit`, the score is scaled
+/// by the Kendall tau distance (the number of pairs in the wrong
+/// relative order) instead of being all-or-nothing.
+pub fn grade_ordering(
+    solution_order: &[usize],
+    submitted: &[usize],
+    partial_credit: bool,
+) -> Result<Grade, String> {
+    if !is_permutation(solution_order, solution_order.len()) {
+        return Err("The problem's solution must order every item exactly once".to_string());
+    }
+    if !is_permutation(submitted, solution_order.len()) {
+        return Err("Submission must order every item exactly once".to_string());
+    }
+
+    let per_item: Vec<ItemGrade> = solution_order
+        .iter()
+        .zip(submitted)
+        .map(|(expected, actual)| ItemGrade {
+            correct: expected == actual,
+            score: 0.0,
+            max: 0.0,
+        })
+        .collect();
+    let pairs = solution_order.len() * solution_order.len().saturating_sub(1) / 2;
+    let score = if submitted == solution_order {
+        1.0
+    } else if partial_credit && pairs > 0 {
+        1.0 - kendall_tau_distance(solution_order, submitted) as f32 / pairs as f32
+    } else {
+        0.0
+    };
+    Ok(Grade {
+        score,
+        max: 1.0,
+        per_item,
+        parts: vec![],
+    })
+}
+
+/// Counts the pairs of items whose relative order differs between
+/// two permutations of the same indices, as checked by [`is_permutation`].
+pub fn kendall_tau_distance(a: &[usize], b: &[usize]) -> usize {
+    let mut rank = vec![0; a.len()];
+    for (position, item) in a.iter().enumerate() {
+        rank[*item] = position;
+    }
+    let ranks: Vec<usize> = b.iter().map(|item| rank[*item]).collect();
+    let mut discordant = 0;
+    for i in 0..ranks.len() {
+        for j in i + 1..ranks.len() {
+            if ranks[i] > ranks[j] {
+                discordant += 1;
+            }
+        }
+    }
+    discordant
+}
+
+/// Grades a matching with one point per left-hand item
+/// that is matched to its partner in `pairs`.
+pub fn grade_matching(
+    left_count: usize,
+    pairs: &[(usize, usize)],
+    submitted: &[Option<usize>],
+) -> Result<Grade, String> {
+    if submitted.len() != left_count {
+        return Err("Submission must have an entry for every item".to_string());
+    }
+    Ok(Grade::from_items(
+        submitted
+            .iter()
+            .enumerate()
+            .map(|(left, right)| {
+                let expected = pairs
+                    .iter()
+                    .find(|(pair_left, _)| *pair_left == left)
+                    .map(|(_, pair_right)| *pair_right);
+                ItemGrade::all_or_nothing(*right == expected)
+            })
+            .collect(),
+    ))
+}
+
+/// Combines the grades of the parts of a multi-part problem, scaling
+/// each part to its weight. Unanswered parts score nothing.
+pub fn grade_parts(weights: &[f32], parts: Vec<Option<Grade>>) -> Grade {
+    let per_item = weights
+        .iter()
+        .zip(&parts)
+        .map(|(weight, grade)| match grade {
+            Some(grade) => ItemGrade {
+                correct: grade.is_correct(),
+                score: if grade.max > 0.0 {
+                    weight * grade.score / grade.max
+                } else {
+                    *weight
+                },
+                max: *weight,
+            },
+            None => ItemGrade {
+                correct: false,
+                score: 0.0,
+                max: *weight,
+            },
+        })
+        .collect();
+    Grade {
+        parts,
+        ..Grade::from_items(per_item)
+    }
+}
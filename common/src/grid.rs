@@ -0,0 +1,444 @@
+use serde::{Deserialize, Serialize};
+
+/// The contents of a grid, row by row. `None` is an empty cell.
+pub type Cells = Vec<Vec<Option<u32>>>;
+
+/// The most rows or columns a grid can have.
+pub const MAX_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum PuzzleKind {
+    /// Every row, column and `box_rows` by `box_cols` box
+    /// holds each number from 1 to the grid size once.
+    Sudoku { box_rows: usize, box_cols: usize },
+    /// Cells are shaded (1) or left blank (0) so that
+    /// the shaded runs match the row and column clues.
+    Nonogram,
+    /// Every row and column holds each number from 1 to the
+    /// grid size once, and the cages make their targets.
+    KenKen,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GridConstraint {
+    /// The lengths of the shaded runs in a row, in order.
+    RowClue { row: usize, runs: Vec<usize> },
+    /// The lengths of the shaded runs in a column, from the top.
+    ColumnClue { col: usize, runs: Vec<usize> },
+    /// The values in `cells` combine under `op` to make `target`.
+    Cage {
+        cells: Vec<(usize, usize)>,
+        op: CageOp,
+        target: u32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CageOp {
+    Add,
+    /// The largest value minus all the others.
+    Subtract,
+    Multiply,
+    /// The largest value divided by all the others, exactly.
+    Divide,
+}
+
+impl CageOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            CageOp::Add => "+",
+            CageOp::Subtract => "−",
+            CageOp::Multiply => "×",
+            CageOp::Divide => "÷",
+        }
+    }
+
+    /// What the values combine to, if anything. A single
+    /// value always combines to itself.
+    fn apply(self, values: &[u32]) -> Option<u64> {
+        let values: Vec<u64> = values.iter().map(|value| u64::from(*value)).collect();
+        let largest = *values.iter().max()?;
+        if values.len() == 1 {
+            return Some(largest);
+        }
+        match self {
+            CageOp::Add => Some(values.iter().sum()),
+            CageOp::Multiply => values.iter().try_fold(1u64, |product, value| product.checked_mul(*value)),
+            CageOp::Subtract => largest.checked_sub(values.iter().sum::<u64>() - largest),
+            CageOp::Divide => {
+                let rest = values
+                    .iter()
+                    .try_fold(1u64, |product, value| product.checked_mul(*value))?
+                    / largest.max(1);
+                if rest > 0 && largest % rest == 0 {
+                    Some(largest / rest)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// A logic puzzle played on a grid. Submissions are checked against
+/// the rules of the puzzle rather than a stored solution, so any
+/// valid solution is accepted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridPuzzle {
+    pub kind: PuzzleKind,
+    pub rows: usize,
+    pub cols: usize,
+    /// Cells filled in from the start, as `(row, col, value)`.
+    #[serde(default)]
+    pub givens: Vec<(usize, usize, u32)>,
+    #[serde(default)]
+    pub constraints: Vec<GridConstraint>,
+}
+
+impl GridPuzzle {
+    /// The smallest value a cell can hold.
+    pub fn min_value(&self) -> u32 {
+        match self.kind {
+            PuzzleKind::Nonogram => 0,
+            _ => 1,
+        }
+    }
+
+    /// The largest value a cell can hold.
+    pub fn max_value(&self) -> u32 {
+        match self.kind {
+            PuzzleKind::Nonogram => 1,
+            _ => self.rows as u32,
+        }
+    }
+
+    pub fn given(&self, row: usize, col: usize) -> Option<u32> {
+        self.givens
+            .iter()
+            .find(|(given_row, given_col, _)| *given_row == row && *given_col == col)
+            .map(|(_, _, value)| *value)
+    }
+
+    /// The grid as it starts out, with only the givens filled in.
+    pub fn initial_cells(&self) -> Cells {
+        (0..self.rows)
+            .map(|row| (0..self.cols).map(|col| self.given(row, col)).collect())
+            .collect()
+    }
+
+    /// The index of the sudoku box or cage a cell belongs to, if any.
+    /// Used to draw region borders.
+    pub fn region(&self, row: usize, col: usize) -> Option<usize> {
+        match self.kind {
+            PuzzleKind::Sudoku { box_rows, box_cols } if box_rows > 0 && box_cols > 0 => {
+                Some(row / box_rows * (self.cols / box_cols) + col / box_cols)
+            }
+            PuzzleKind::Sudoku { .. } => None,
+            _ => self
+                .constraints
+                .iter()
+                .position(|constraint| match constraint {
+                    GridConstraint::Cage { cells, .. } => cells.contains(&(row, col)),
+                    _ => false,
+                }),
+        }
+    }
+
+    /// Checks that the puzzle itself is well formed.
+    /// returns a user-displayable error message if it is not.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rows == 0 || self.cols == 0 {
+            return Err("The grid must have at least one row and column".to_string());
+        }
+        if self.rows > MAX_SIZE || self.cols > MAX_SIZE {
+            return Err(format!(
+                "The grid can be at most {} by {}",
+                MAX_SIZE, MAX_SIZE
+            ));
+        }
+        match self.kind {
+            PuzzleKind::Sudoku { box_rows, box_cols } => match box_rows.checked_mul(box_cols) {
+                Some(size) if self.rows == self.cols && size == self.rows => (),
+                Some(size) => {
+                    return Err(format!(
+                        "A sudoku with {} by {} boxes must be {} by {}",
+                        box_rows, box_cols, size, size
+                    ));
+                }
+                None => return Err("The sudoku's boxes are too large".to_string()),
+            },
+            PuzzleKind::KenKen => {
+                if self.rows != self.cols {
+                    return Err("A KenKen grid must be square".to_string());
+                }
+            }
+            PuzzleKind::Nonogram => (),
+        }
+        for (row, col, value) in &self.givens {
+            if !self.in_bounds(*row, *col) {
+                return Err(format!("Given at row {}, column {} is off the grid", row + 1, col + 1));
+            }
+            if *value < self.min_value() || *value > self.max_value() {
+                return Err(format!("Given at row {}, column {} is out of range", row + 1, col + 1));
+            }
+        }
+        for constraint in &self.constraints {
+            match constraint {
+                GridConstraint::RowClue { row, .. } if *row >= self.rows => {
+                    return Err(format!("There is no row {} to give a clue for", row + 1));
+                }
+                GridConstraint::ColumnClue { col, .. } if *col >= self.cols => {
+                    return Err(format!("There is no column {} to give a clue for", col + 1));
+                }
+                GridConstraint::Cage { cells, .. } => {
+                    if cells.is_empty() {
+                        return Err("A cage must have at least one cell".to_string());
+                    }
+                    if cells.iter().any(|(row, col)| !self.in_bounds(*row, *col)) {
+                        return Err("A cage has cells off the grid".to_string());
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks a completed grid against the rules of the puzzle.
+    /// returns Ok if the grid solves the puzzle and if it does not
+    /// it returns a user-displayable description of the first broken rule.
+    pub fn check(&self, cells: &[Vec<Option<u32>>]) -> Result<(), String> {
+        self.validate()?;
+        if cells.len() != self.rows || cells.iter().any(|row| row.len() != self.cols) {
+            return Err(format!("The grid must be {} by {}", self.rows, self.cols));
+        }
+        // Blank nonogram cells may be left empty, everything else must be filled
+        let blank = match self.kind {
+            PuzzleKind::Nonogram => Some(0),
+            _ => None,
+        };
+        let mut grid = vec![vec![0; self.cols]; self.rows];
+        for (row, values) in cells.iter().enumerate() {
+            for (col, value) in values.iter().enumerate() {
+                let value = value
+                    .or(blank)
+                    .ok_or_else(|| "Every cell must be filled in".to_string())?;
+                if value < self.min_value() || value > self.max_value() {
+                    return Err(format!("Row {}, column {} is out of range", row + 1, col + 1));
+                }
+                grid[row][col] = value;
+            }
+        }
+        for (row, col, value) in &self.givens {
+            if grid[*row][*col] != *value {
+                return Err(format!("Row {}, column {} must stay {}", row + 1, col + 1, value));
+            }
+        }
+
+        if self.kind != PuzzleKind::Nonogram {
+            for i in 0..self.rows {
+                if let Some(value) = repeated(grid[i].iter().copied()) {
+                    return Err(format!("Row {} repeats {}", i + 1, value));
+                }
+                if let Some(value) = repeated(grid.iter().map(|row| row[i])) {
+                    return Err(format!("Column {} repeats {}", i + 1, value));
+                }
+            }
+        }
+        if let PuzzleKind::Sudoku { box_rows, box_cols } = self.kind {
+            for b in 0..self.rows {
+                let (top, left) = (b / (self.cols / box_cols) * box_rows, b % (self.cols / box_cols) * box_cols);
+                let values = (top..top + box_rows)
+                    .flat_map(|row| (left..left + box_cols).map(move |col| (row, col)))
+                    .map(|(row, col)| grid[row][col]);
+                if let Some(value) = repeated(values) {
+                    return Err(format!("Box {} repeats {}", b + 1, value));
+                }
+            }
+        }
+
+        for constraint in &self.constraints {
+            match constraint {
+                GridConstraint::RowClue { row, runs } => {
+                    if shaded_runs(grid[*row].iter().map(|value| *value > 0)) != clue(runs) {
+                        return Err(format!("Row {} doesn't match its clue", row + 1));
+                    }
+                }
+                GridConstraint::ColumnClue { col, runs } => {
+                    if shaded_runs(grid.iter().map(|row| row[*col] > 0)) != clue(runs) {
+                        return Err(format!("Column {} doesn't match its clue", col + 1));
+                    }
+                }
+                GridConstraint::Cage { cells, op, target } => {
+                    let values: Vec<u32> = cells.iter().map(|(row, col)| grid[*row][*col]).collect();
+                    if op.apply(&values) != Some(u64::from(*target)) {
+                        let (row, col) = cells[0];
+                        return Err(format!(
+                            "The cage at row {}, column {} doesn't make {}{}",
+                            row + 1,
+                            col + 1,
+                            target,
+                            op.symbol()
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts the puzzle's solutions, stopping once `limit` are found.
+    /// The creation studio uses a limit of 2 to warn about puzzles
+    /// without a unique solution.
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        if self.validate().is_err() {
+            return 0;
+        }
+        let mut cells = self.initial_cells();
+        let mut found = 0;
+        self.search(&mut cells, 0, limit, &mut found);
+        found
+    }
+
+    /// Fills the cells in reading order, backtracking as
+    /// soon as a rule can no longer be satisfied.
+    fn search(&self, cells: &mut Cells, position: usize, limit: usize, found: &mut usize) {
+        if *found >= limit {
+            return;
+        }
+        if position == self.rows * self.cols {
+            *found += 1;
+            return;
+        }
+        let (row, col) = (position / self.cols, position % self.cols);
+        if cells[row][col].is_some() {
+            if self.consistent_at(cells, row, col) {
+                self.search(cells, position + 1, limit, found);
+            }
+            return;
+        }
+        for value in self.min_value()..=self.max_value() {
+            cells[row][col] = Some(value);
+            if self.consistent_at(cells, row, col) {
+                self.search(cells, position + 1, limit, found);
+            }
+        }
+        cells[row][col] = None;
+    }
+
+    /// Whether the grid can still be completed given the value at `(row, col)`,
+    /// assuming every cell before it in reading order is filled in.
+    fn consistent_at(&self, cells: &[Vec<Option<u32>>], row: usize, col: usize) -> bool {
+        let value = match cells[row][col] {
+            Some(value) => value,
+            None => return true,
+        };
+        if value < self.min_value() || value > self.max_value() {
+            return false;
+        }
+        let same = |other_row: usize, other_col: usize| {
+            (other_row, other_col) != (row, col) && cells[other_row][other_col] == Some(value)
+        };
+        if self.kind != PuzzleKind::Nonogram
+            && ((0..self.cols).any(|other| same(row, other))
+                || (0..self.rows).any(|other| same(other, col)))
+        {
+            return false;
+        }
+        if let PuzzleKind::Sudoku { box_rows, box_cols } = self.kind {
+            let (top, left) = (row / box_rows * box_rows, col / box_cols * box_cols);
+            if (top..top + box_rows).any(|other_row| (left..left + box_cols).any(|other_col| same(other_row, other_col))) {
+                return false;
+            }
+        }
+
+        let shaded = |row: usize, col: usize| cells[row][col].unwrap_or(0) > 0;
+        self.constraints.iter().all(|constraint| match constraint {
+            GridConstraint::RowClue { row: clue_row, runs } if *clue_row == row => {
+                line_feasible(&(0..=col).map(|col| shaded(row, col)).collect::<Vec<_>>(), &clue(runs), self.cols)
+            }
+            GridConstraint::ColumnClue { col: clue_col, runs } if *clue_col == col => {
+                line_feasible(&(0..=row).map(|row| shaded(row, col)).collect::<Vec<_>>(), &clue(runs), self.rows)
+            }
+            GridConstraint::Cage { cells: cage, op, target } if cage.contains(&(row, col)) => {
+                let values: Vec<u32> = cage.iter().filter_map(|(row, col)| cells[*row][*col]).collect();
+                let unknown = (cage.len() - values.len()) as u32;
+                let target = u64::from(*target);
+                if unknown == 0 {
+                    op.apply(&values) == Some(target)
+                } else {
+                    // Every missing value is at least 1
+                    match op {
+                        CageOp::Add => {
+                            values.iter().map(|value| u64::from(*value)).sum::<u64>() + u64::from(unknown) <= target
+                        }
+                        CageOp::Multiply => match op.apply(&values) {
+                            Some(product) => product <= target && target % product.max(1) == 0,
+                            None => false,
+                        },
+                        CageOp::Subtract | CageOp::Divide => true,
+                    }
+                }
+            }
+            _ => true,
+        })
+    }
+
+    fn in_bounds(&self, row: usize, col: usize) -> bool {
+        row < self.rows && col < self.cols
+    }
+}
+
+/// The first value that appears more than once, if any.
+fn repeated(values: impl Iterator<Item = u32>) -> Option<u32> {
+    let mut seen = vec![];
+    for value in values {
+        if seen.contains(&value) {
+            return Some(value);
+        }
+        seen.push(value);
+    }
+    None
+}
+
+/// A clue without zero-length runs, so `[0]` and `[]` both mean an empty line.
+fn clue(runs: &[usize]) -> Vec<usize> {
+    runs.iter().copied().filter(|run| *run > 0).collect()
+}
+
+fn shaded_runs(line: impl Iterator<Item = bool>) -> Vec<usize> {
+    let mut runs = vec![];
+    let mut current = 0;
+    for shaded in line {
+        if shaded {
+            current += 1;
+        } else if current > 0 {
+            runs.push(current);
+            current = 0;
+        }
+    }
+    if current > 0 {
+        runs.push(current);
+    }
+    runs
+}
+
+/// Whether a line of length `len` starting with `prefix` can still match `runs`.
+fn line_feasible(prefix: &[bool], runs: &[usize], len: usize) -> bool {
+    let mut found = shaded_runs(prefix.iter().copied());
+    let open = if prefix.last() == Some(&true) { found.pop() } else { None };
+    if found.len() > runs.len() || found[..] != runs[..found.len()] {
+        return false;
+    }
+    let remaining = &runs[found.len()..];
+    let needed = match open {
+        Some(open) => match remaining.split_first() {
+            Some((current, rest)) if open <= *current => {
+                current - open + rest.iter().map(|run| run + 1).sum::<usize>()
+            }
+            _ => return false,
+        },
+        None => remaining.iter().sum::<usize>() + remaining.len().saturating_sub(1),
+    };
+    needed <= len - prefix.len()
+}
@@ -1,2 +1,16 @@
+#[macro_use]
+mod macros;
+
+pub mod complex;
+pub mod expression;
+pub mod grading;
+pub mod grid;
+pub mod pow;
 pub mod problems;
+pub mod random;
+pub mod reports;
+pub mod rational;
+pub mod text;
+pub mod units;
 pub mod user;
+pub mod vetting;
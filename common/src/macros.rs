@@ -0,0 +1,28 @@
+/// Implements `Display` and `FromStr` with the same
+/// snake_case labels serde uses, for storing and showing them.
+macro_rules! labels {
+    ($name:ident { $($variant:ident => $label:expr),+ $(,)? }) => {
+        impl $name {
+            pub const ALL: &'static [$name] = &[$($name::$variant),+];
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(match self {
+                    $($name::$variant => $label),+
+                })
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($label => Ok($name::$variant),)+
+                    _ => Err(format!("Unrecognized {} {}", stringify!($name), s)),
+                }
+            }
+        }
+    };
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Work the server asks for before signing someone up, to make
+/// mass account creation expensive. The client finds a nonce such that
+/// `SHA-256(challenge || name || nonce)` starts with `difficulty` zero bits.
+/// Including the account name means every account needs its own work,
+/// even if a challenge is replayed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Challenge {
+    pub challenge: String,
+    pub difficulty: u32,
+}
+
+impl Challenge {
+    pub fn is_solved_by(&self, name: &str, nonce: u64) -> bool {
+        leading_zero_bits(&hash(&self.challenge, name, nonce)) >= self.difficulty
+    }
+
+    /// Tries nonces in order until one works, which takes about
+    /// `2^difficulty` hashes.
+    pub fn solve(&self, name: &str) -> u64 {
+        (0..).find(|nonce| self.is_solved_by(name, *nonce)).unwrap()
+    }
+}
+
+fn hash(challenge: &str, name: &str, nonce: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    // Length-prefixed so a challenge and name can't be split differently
+    hasher.update((challenge.len() as u64).to_le_bytes());
+    hasher.update(challenge.as_bytes());
+    hasher.update(name.as_bytes());
+    hasher.update(nonce.to_le_bytes());
+    hasher.finalize().into()
+}
+
+fn leading_zero_bits(bytes: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in bytes {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
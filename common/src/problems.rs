@@ -1,4 +1,16 @@
+use crate::{
+    complex::Complex,
+    expression::Expr,
+    grading::{self, ChecklistScoring, Grade, ItemGrade, Submission},
+    grid::GridPuzzle,
+    random::{self, SeededRng},
+    rational::{self, Rational},
+    text::TextMatchOptions,
+    units::{Quantity, Tolerance, Unit},
+};
+use num_bigint::BigInt;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Problem {
@@ -10,23 +22,82 @@ pub struct Problem {
     pub prompt: String,
     pub content: ProblemContent,
     pub explanation: String,
+    /// Hidden by a moderator, so only moderators see it.
+    #[serde(default)]
+    pub hidden: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewProblem {
     pub owner_id: i32,
-    pub topic: Topic,
+    pub topic_id: i32,
     pub tags: Vec<String>,
     pub prompt: String,
     pub content: ProblemContent,
     pub explanation: String,
 }
 
+/// A subject problems are filed under. Topics form a tree
+/// (e.g. Math > Number Theory) and are managed by admins.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-pub enum Topic {
-    Math,
-    Trivia,
-    Logic,
+pub struct Topic {
+    pub id: i32,
+    /// A short, url-safe identifier such as `number-theory`.
+    pub slug: String,
+    pub name: String,
+    pub description: String,
+    pub parent_id: Option<i32>,
+    /// An emoji or short text shown next to the name.
+    pub icon: Option<String>,
+}
+
+impl Topic {
+    /// The topics directly under `parent` (or the root topics if `None`).
+    pub fn children(topics: &[Topic], parent: Option<i32>) -> Vec<&Topic> {
+        topics
+            .iter()
+            .filter(|topic| topic.parent_id == parent)
+            .collect()
+    }
+
+    /// The name with its icon, if any, e.g. `∑ Math`.
+    pub fn display_name(&self) -> String {
+        match &self.icon {
+            Some(icon) => format!("{} {}", icon, self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewTopic {
+    pub slug: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+impl NewTopic {
+    /// Checks the topic's fields, returning a
+    /// user-displayable error message if one is invalid.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.slug.is_empty()
+            || !self
+                .slug
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err("A slug must be lowercase letters, digits and dashes".to_string());
+        }
+        if self.name.trim().is_empty() {
+            return Err("A topic must have a name".to_string());
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,7 +113,94 @@ pub enum ProblemContent {
     Checklist {
         options: Vec<String>,
         solution: Vec<usize>,
+        #[serde(default)]
+        scoring: ChecklistScoring,
+    },
+    /// `items` are shown shuffled (see [`display_order`]), and
+    /// `solution_order` lists their indices in the correct order.
+    Ordering {
+        items: Vec<String>,
+        solution_order: Vec<usize>,
+        #[serde(default)]
+        partial_credit: bool,
+    },
+    /// Each `(left, right)` in `pairs` matches `left[left]` with `right[right]`.
+    /// `right` may contain extra distractors that match nothing.
+    Matching {
+        left: Vec<String>,
+        right: Vec<String>,
+        pairs: Vec<(usize, usize)>,
     },
+    /// Several sub-questions sharing the problem's prompt,
+    /// e.g. parts (a), (b) and (c) of an olympiad problem.
+    MultiPart { parts: Vec<Part> },
+    /// Fill in the blanks: the prompt marks blank `n` as `[[n]]`
+    /// (counting from 1), and `blanks[n - 1]` says how it's answered.
+    Cloze { blanks: Vec<Blank> },
+    /// A "Rosetta Stone" puzzle: example sentences in `languages.0`
+    /// with their translations into `languages.1`, followed by
+    /// items to translate in either direction.
+    Translation {
+        languages: (String, String),
+        examples: Vec<(String, String)>,
+        items: Vec<TranslationItem>,
+        #[serde(default = "TextMatchOptions::normalized")]
+        options: TextMatchOptions,
+    },
+    /// A logic puzzle such as a sudoku, graded by its rules.
+    Grid(GridPuzzle),
+    /// A numeric problem whose prompt contains `{{...}}` placeholders over
+    /// `variables`. Every user gets their own instance of the variables,
+    /// and the answer is the value of the `answer` formula for it.
+    Template {
+        variables: Vec<TemplateVariable>,
+        /// Comparisons such as `a != b` or `b^2 - 4a*c >= 0`
+        /// that every instance must satisfy.
+        #[serde(default)]
+        constraints: Vec<String>,
+        answer: String,
+        precision: f64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Part {
+    pub prompt: String,
+    pub content: ProblemContent,
+    /// How much this part counts towards the problem's total score.
+    #[serde(default = "Part::default_weight")]
+    pub weight: f32,
+}
+
+impl Part {
+    fn default_weight() -> f32 {
+        1.0
+    }
+}
+
+/// The version of the stored `ProblemContent` format. Bump it and add
+/// an entry to `CONTENT_UPGRADES` whenever a change to `ProblemContent`
+/// would stop content stored by an older version from deserializing.
+pub const CONTENT_VERSION: i32 = 1;
+
+/// `CONTENT_UPGRADES[i]` rewrites content stored at version `i + 1`
+/// into the format of version `i + 2`.
+const CONTENT_UPGRADES: [fn(&mut serde_json::Value); (CONTENT_VERSION - 1) as usize] = [];
+
+impl ProblemContent {
+    /// Deserializes content stored at `version`, upgrading it to the current format first.
+    pub fn from_stored(version: i32, mut value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        if version < 1 || version > CONTENT_VERSION {
+            return Err(serde::de::Error::custom(format!(
+                "unsupported content version {} (current is {})",
+                version, CONTENT_VERSION
+            )));
+        }
+        for upgrade in &CONTENT_UPGRADES[(version - 1) as usize..] {
+            upgrade(&mut value);
+        }
+        serde_json::from_value(value)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -50,6 +208,13 @@ pub enum ProblemType {
     FreeResponse,
     MultipleChoice,
     Checklist,
+    Ordering,
+    Matching,
+    MultiPart,
+    Template,
+    Cloze,
+    Grid,
+    Translation,
 }
 
 impl ProblemContent {
@@ -63,27 +228,448 @@ impl ProblemContent {
                 options: _,
                 solution: _,
             } => ProblemType::MultipleChoice,
-            ProblemContent::Checklist {
-                options: _,
-                solution: _,
-            } => ProblemType::Checklist,
+            ProblemContent::Checklist { .. } => ProblemType::Checklist,
+            ProblemContent::Ordering { .. } => ProblemType::Ordering,
+            ProblemContent::Matching { .. } => ProblemType::Matching,
+            ProblemContent::MultiPart { .. } => ProblemType::MultiPart,
+            ProblemContent::Template { .. } => ProblemType::Template,
+            ProblemContent::Cloze { .. } => ProblemType::Cloze,
+            ProblemContent::Grid(_) => ProblemType::Grid,
+            ProblemContent::Translation { .. } => ProblemType::Translation,
+        }
+    }
+
+    /// The values of a template's variables in the instance picked by `seed`
+    /// (see [`random::user_problem_seed`]). Other content has no variables.
+    pub fn template_instance(&self, seed: u64) -> Result<HashMap<String, f64>, String> {
+        match self {
+            ProblemContent::Template {
+                variables,
+                constraints,
+                ..
+            } => sample_template(variables, constraints, seed),
+            _ => Ok(HashMap::new()),
+        }
+    }
+
+    /// Fills in the placeholders of a template's prompt (or explanation)
+    /// for the instance picked by `seed`. Other text is returned as is.
+    pub fn instantiate_text(&self, text: &str, seed: u64) -> Result<String, String> {
+        match self {
+            ProblemContent::Template { .. } => fill_template(text, &self.template_instance(seed)?),
+            _ => Ok(text.to_string()),
+        }
+    }
+
+    /// Checks the parts of the content that grading relies on being well formed.
+    /// returns a user-displayable error message if they are not.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ProblemContent::Ordering {
+                items,
+                solution_order,
+                ..
+            } => {
+                if !grading::is_permutation(solution_order, items.len()) {
+                    return Err("The solution must order every item exactly once".to_string());
+                }
+                Ok(())
+            }
+            ProblemContent::MultiPart { parts } => parts
+                .iter()
+                .try_for_each(|part| part.content.validate()),
+            ProblemContent::Grid(puzzle) => puzzle.validate(),
+            ProblemContent::Template { answer, .. } => {
+                // Constraints that can't be met or an answer that can't be
+                // worked out would leave users with a problem they can't open
+                let instance = self.template_instance(0)?;
+                Expr::parse(answer)?.eval(&instance)?;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Grades a submission against this content, using the instance
+    /// picked by `seed` for templates. Errors if the submission
+    /// is for a different type of problem.
+    pub fn grade(&self, submission: &Submission, seed: u64) -> Result<Grade, String> {
+        match (self, submission) {
+            (ProblemContent::FreeResponse { solution, .. }, Submission::FreeResponse(response)) => {
+                Ok(Grade::single(
+                    solution.iter().any(|solution| solution.check(response.clone())),
+                ))
+            }
+            (ProblemContent::MultipleChoice { solution, .. }, Submission::MultipleChoice(choice)) => {
+                Ok(Grade::single(solution == choice))
+            }
+            (
+                ProblemContent::Checklist {
+                    options,
+                    solution,
+                    scoring,
+                },
+                Submission::Checklist(selected),
+            ) => Ok(scoring.grade(options.len(), solution, selected)),
+            (
+                ProblemContent::Ordering {
+                    solution_order,
+                    partial_credit,
+                    ..
+                },
+                Submission::Ordering(order),
+            ) => grading::grade_ordering(solution_order, order, *partial_credit),
+            (ProblemContent::Matching { left, pairs, .. }, Submission::Matching(matches)) => {
+                grading::grade_matching(left.len(), pairs, matches)
+            }
+            (ProblemContent::MultiPart { parts }, Submission::MultiPart(submissions)) => {
+                if submissions.len() != parts.len() {
+                    return Err("Submission must have an entry for every part".to_string());
+                }
+                let part_grades = parts
+                    .iter()
+                    .zip(submissions)
+                    .map(|(part, submission)| match submission {
+                        Some(submission) => part.content.grade(submission, seed).map(Some),
+                        None => Ok(None),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(grading::grade_parts(
+                    &parts.iter().map(|part| part.weight).collect::<Vec<_>>(),
+                    part_grades,
+                ))
+            }
+            (ProblemContent::Cloze { blanks }, Submission::Cloze(answers)) => {
+                if answers.len() != blanks.len() {
+                    return Err("Submission must have an entry for every blank".to_string());
+                }
+                Ok(Grade::from_items(
+                    blanks
+                        .iter()
+                        .zip(answers)
+                        .map(|(blank, answer)| match answer {
+                            Some(answer) => blank.check(answer).map(ItemGrade::all_or_nothing),
+                            None => Ok(ItemGrade::all_or_nothing(false)),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                ))
+            }
+            (
+                ProblemContent::Translation { items, options, .. },
+                Submission::Translation(translations),
+            ) => {
+                if translations.len() != items.len() {
+                    return Err("Submission must have an entry for every item".to_string());
+                }
+                Ok(Grade::from_items(
+                    items
+                        .iter()
+                        .zip(translations)
+                        .map(|(item, translation)| {
+                            ItemGrade::all_or_nothing(translation.as_ref().is_some_and(|translation| {
+                                item.answers.iter().any(|answer| options.matches(answer, translation))
+                            }))
+                        })
+                        .collect(),
+                ))
+            }
+            (ProblemContent::Grid(puzzle), Submission::Grid(cells)) => {
+                Ok(Grade::single(puzzle.check(cells).is_ok()))
+            }
+            (ProblemContent::Template { answer, precision, .. }, Submission::FreeResponse(response)) => {
+                let expected = Expr::parse(answer)
+                    .and_then(|answer| answer.eval(&self.template_instance(seed)?))
+                    .map_err(|error| format!("This problem has an invalid answer: {}", error))?;
+                Ok(Grade::single(
+                    Expr::parse(response)
+                        .and_then(|response| response.eval(&HashMap::new()))
+                        .is_ok_and(|actual| (actual - expected).abs() < *precision),
+                ))
+            }
+            _ => Err("Submission does not match the problem type".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationItem {
+    pub text: String,
+    /// If set, `text` is in the second language and is
+    /// translated back into the first.
+    #[serde(default)]
+    pub reverse: bool,
+    /// Every accepted translation.
+    pub answers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Blank {
+    /// Typed in and checked like a free response problem.
+    FreeResponse {
+        #[serde(default)]
+        restrictions: Vec<FreeResponseRestriction>,
+        solution: Vec<FreeResponseSolution>,
+    },
+    /// Picked from a dropdown of `options`.
+    Dropdown { options: Vec<String>, solution: usize },
+}
+
+impl Blank {
+    /// Checks if an answer fills the blank correctly.
+    /// Errors if the answer is for a different kind of blank.
+    pub fn check(&self, answer: &Submission) -> Result<bool, String> {
+        match (self, answer) {
+            (Blank::FreeResponse { solution, .. }, Submission::FreeResponse(response)) => Ok(
+                solution.iter().any(|solution| solution.check(response.clone())),
+            ),
+            (Blank::Dropdown { solution, .. }, Submission::MultipleChoice(choice)) => {
+                Ok(solution == choice)
+            }
+            _ => Err("Answer does not match the kind of blank".to_string()),
+        }
+    }
+}
+
+/// A piece of a cloze prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClozeSegment {
+    Text(String),
+    /// The blank with the given (zero-based) index.
+    Blank(usize),
+}
+
+/// Splits text into literal text and `[[n]]` blank markers.
+/// Anything between `[[` and `]]` that isn't a positive number is kept as text.
+pub fn cloze_segments(text: &str) -> Vec<ClozeSegment> {
+    let mut segments = vec![];
+    let mut literal = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        let blank = rest[start + 2..].find("]]").and_then(|end| {
+            let number = rest[start + 2..start + 2 + end].trim().parse::<usize>().ok()?;
+            Some((number.checked_sub(1)?, start + 2 + end + 2))
+        });
+        match blank {
+            Some((index, after)) => {
+                literal.push_str(&rest[..start]);
+                if !literal.is_empty() {
+                    segments.push(ClozeSegment::Text(std::mem::take(&mut literal)));
+                }
+                segments.push(ClozeSegment::Blank(index));
+                rest = &rest[after..];
+            }
+            None => {
+                literal.push_str(&rest[..start + 2]);
+                rest = &rest[start + 2..];
+            }
+        }
+    }
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        segments.push(ClozeSegment::Text(literal));
+    }
+    segments
+}
+
+/// A variable of a template, drawn from `values`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateVariable {
+    pub name: String,
+    pub values: VariableValues,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum VariableValues {
+    /// An integer from `start` to `end` inclusive.
+    IntegerRange { start: i64, end: i64 },
+    /// A real number in `[start, end)`, rounded to `decimals` places.
+    RealRange {
+        start: f64,
+        end: f64,
+        #[serde(default)]
+        decimals: u32,
+    },
+    /// One of the listed values.
+    OneOf(Vec<f64>),
+}
+
+impl VariableValues {
+    fn sample(&self, rng: &mut SeededRng) -> Result<f64, String> {
+        match self {
+            VariableValues::IntegerRange { start, end } => {
+                if end < start {
+                    return Err(format!("The range {} to {} is empty", start, end));
+                }
+                let too_wide = || format!("The range {} to {} is too wide", start, end);
+                let span = end.checked_sub(*start).ok_or_else(too_wide)? as u64 + 1;
+                let offset = rng.below(span) as i64;
+                Ok(start.checked_add(offset).ok_or_else(too_wide)? as f64)
+            }
+            VariableValues::RealRange {
+                start,
+                end,
+                decimals,
+            } => {
+                let scale = 10f64.powi(*decimals as i32);
+                Ok((rng.range_f64(*start, *end) * scale).round() / scale)
+            }
+            VariableValues::OneOf(values) => {
+                if values.is_empty() {
+                    return Err("A variable has no values to choose from".to_string());
+                }
+                Ok(values[rng.below(values.len() as u64) as usize])
+            }
+        }
+    }
+}
+
+/// How many times to redraw a template's variables
+/// before giving up on satisfying its constraints.
+const TEMPLATE_ATTEMPTS: usize = 1000;
+
+fn sample_template(
+    variables: &[TemplateVariable],
+    constraints: &[String],
+    seed: u64,
+) -> Result<HashMap<String, f64>, String> {
+    let constraints = constraints
+        .iter()
+        .map(|constraint| Constraint::parse(constraint))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut rng = SeededRng::new(seed);
+    for _ in 0..TEMPLATE_ATTEMPTS {
+        let mut instance = HashMap::new();
+        for variable in variables {
+            instance.insert(variable.name.clone(), variable.values.sample(&mut rng)?);
+        }
+        let mut satisfied = true;
+        for constraint in &constraints {
+            if !constraint.holds(&instance)? {
+                satisfied = false;
+                break;
+            }
         }
+        if satisfied {
+            return Ok(instance);
+        }
+    }
+    Err("No values of this problem's variables satisfy its constraints".to_string())
+}
+
+/// A comparison between two expressions, e.g. `a + b < 10`.
+struct Constraint {
+    lhs: Expr,
+    relation: &'static str,
+    rhs: Expr,
+}
+
+impl Constraint {
+    /// Two-character relations come first so that
+    /// `<=` isn't mistaken for `<`.
+    const RELATIONS: [&'static str; 7] = ["<=", ">=", "!=", "==", "<", ">", "="];
+
+    fn parse(constraint: &str) -> Result<Self, String> {
+        let (position, relation) = Self::RELATIONS
+            .iter()
+            .find_map(|relation| constraint.find(relation).map(|position| (position, *relation)))
+            .ok_or_else(|| format!("\"{}\" is not a comparison", constraint))?;
+        Ok(Self {
+            lhs: Expr::parse(&constraint[..position])?,
+            relation,
+            rhs: Expr::parse(&constraint[position + relation.len()..])?,
+        })
+    }
+
+    fn holds(&self, instance: &HashMap<String, f64>) -> Result<bool, String> {
+        let lhs = self.lhs.eval(instance)?;
+        let rhs = self.rhs.eval(instance)?;
+        let equal = (lhs - rhs).abs() < 1e-9;
+        Ok(match self.relation {
+            "<=" => lhs < rhs || equal,
+            ">=" => lhs > rhs || equal,
+            "!=" => !equal,
+            "<" => lhs < rhs && !equal,
+            ">" => lhs > rhs && !equal,
+            _ => equal,
+        })
     }
 }
 
+/// Replaces every `{{expression}}` in `text` with the
+/// expression's value for the given variables.
+pub fn fill_template(text: &str, instance: &HashMap<String, f64>) -> Result<String, String> {
+    let mut filled = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .map(|end| start + end)
+            .ok_or_else(|| "A \"{{\" placeholder is never closed".to_string())?;
+        filled.push_str(&rest[..start]);
+        let value = Expr::parse(&rest[start + 2..end])?.eval(instance)?;
+        filled.push_str(&format_template_value(value));
+        rest = &rest[end + 2..];
+    }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+/// Formats a value without float noise, e.g. `3` rather than `3.0000000000000004`.
+fn format_template_value(value: f64) -> String {
+    let rounded = (value * 1e6).round() / 1e6;
+    if rounded == rounded.trunc() && rounded.abs() < 1e15 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+/// The order `user_id` sees the items of an ordering problem or the
+/// right-hand options of a matching problem in. It is shuffled differently
+/// for every user but never changes for the same user and problem.
+pub fn display_order(user_id: i32, problem_id: i32, count: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..count).collect();
+    SeededRng::new(random::user_problem_seed(user_id, problem_id)).shuffle(&mut order);
+    order
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FreeResponseRestriction {
     Imaginary,
+    Expression,
     Integer,
     Natural,
+    /// The input must be an exact fraction, decimal or mixed number,
+    /// optionally written in lowest terms.
+    Rational {
+        #[serde(default)]
+        lowest_terms: bool,
+    },
+    /// The input must be a number with units compatible with `unit`
+    /// (if given), written to exactly `sig_figs` significant figures (if given).
+    Quantity {
+        #[serde(default)]
+        unit: Option<String>,
+        #[serde(default)]
+        sig_figs: Option<usize>,
+    },
+    MinCharacterLength(usize),
     MaxCharacterLength(usize),
+    /// The whole input must match `pattern`, otherwise
+    /// `message` (or a generic message) is shown.
+    Regex {
+        pattern: String,
+        #[serde(default)]
+        message: Option<String>,
+    },
     RealInRange {
         start: Option<f64>,
         end: Option<f64>,
     },
+    /// Bounds the real and imaginary parts of a complex input separately.
     ImaginaryInRange {
-        start: Option<f64>,
-        end: Option<f64>,
+        start: Option<Complex>,
+        end: Option<Complex>,
     },
 }
 
@@ -93,9 +679,12 @@ impl FreeResponseRestriction {
     /// it returns a user-displayable error message.
     pub fn check(&self, response: String) -> Result<(), String> {
         match self {
-            FreeResponseRestriction::Imaginary => {
-                unimplemented!("Imaginary numbers not yet supported as a solution")
-            }
+            FreeResponseRestriction::Imaginary => Complex::parse(&response)
+                .map(|_| ())
+                .map_err(|_| "Input must be a complex number (e.g. 3+4i)".to_string()),
+            FreeResponseRestriction::Expression => Expr::parse(&response)
+                .map(|_| ())
+                .map_err(|error| format!("Input must be a valid expression: {}", error)),
             FreeResponseRestriction::Integer => if response.parse::<i32>().is_ok() {
                 Ok(())
             } else {
@@ -106,11 +695,53 @@ impl FreeResponseRestriction {
             } else {
                 Err("Input must a natural number".to_string())
             },
-            FreeResponseRestriction::MaxCharacterLength(length) => if response.len() < *length {
+            FreeResponseRestriction::Rational { lowest_terms } => match Rational::parse(&response) {
+                Ok(parsed) => {
+                    if *lowest_terms && !parsed.lowest_terms {
+                        Err("Input must be a fraction in lowest terms".to_string())
+                    } else {
+                        Ok(())
+                    }
+                }
+                Err(error) => Err(error),
+            },
+            FreeResponseRestriction::Quantity { unit, sig_figs } => {
+                let quantity = Quantity::parse(&response)?;
+                if let Some(unit) = unit {
+                    let expected = Unit::parse(unit)?;
+                    if !quantity.unit.is_compatible(&expected) {
+                        return Err(format!("Input must have units compatible with {}", unit));
+                    }
+                }
+                if let Some(sig_figs) = sig_figs {
+                    if quantity.sig_figs != *sig_figs {
+                        return Err(format!(
+                            "Input must be written to {} significant figures",
+                            sig_figs
+                        ));
+                    }
+                }
+                Ok(())
+            }
+            FreeResponseRestriction::MinCharacterLength(length) => if response.chars().count() >= *length {
+                Ok(())
+            } else {
+                Err(format!("Input must be {} characters or more", length))
+            },
+            FreeResponseRestriction::MaxCharacterLength(length) => if response.chars().count() <= *length {
                 Ok(())
             } else {
                 Err(format!("Input must be {} characters or less", length))
             },
+            FreeResponseRestriction::Regex { pattern, message } => {
+                match TextMatchOptions::default().matches_pattern(pattern, &response) {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err(message
+                        .clone()
+                        .unwrap_or_else(|| "Input is not in the expected format".to_string())),
+                    Err(_) => Err("This problem has an invalid input format".to_string()),
+                }
+            }
             FreeResponseRestriction::RealInRange { start, end } => match response.parse::<f64>() {
                 Ok(num) => {
                     if let Some(start) = start {
@@ -127,19 +758,82 @@ impl FreeResponseRestriction {
                 }
                 Err(_) => Err("Input must be a real number".to_string()),
             },
-            FreeResponseRestriction::ImaginaryInRange { start: _, end: _ } => {
-                unimplemented!("Imaginary numbers not yet supported as a solution")
+            FreeResponseRestriction::ImaginaryInRange { start, end } => {
+                match Complex::parse(&response) {
+                    Ok(num) => {
+                        if let Some(start) = start {
+                            if num.re < start.re {
+                                return Err(format!("Real part must be greater than {}", start.re));
+                            }
+                            if num.im < start.im {
+                                return Err(format!(
+                                    "Imaginary part must be greater than {}",
+                                    start.im
+                                ));
+                            }
+                        }
+                        if let Some(end) = end {
+                            if num.re > end.re {
+                                return Err(format!("Real part must be less than {}", end.re));
+                            }
+                            if num.im > end.im {
+                                return Err(format!("Imaginary part must be less than {}", end.im));
+                            }
+                        }
+                        Ok(())
+                    }
+                    Err(_) => Err("Input must be a complex number (e.g. 3+4i)".to_string()),
+                }
             }
         }
     }
-
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FreeResponseSolution {
     RealEquals { eq: f64, precision: f64 },
-    ImaginaryEquals { eq: f64, precision: f64 },
-    TextEquals { eq: String },
+    ImaginaryEquals { eq: Complex, precision: f64 },
+    /// Matches `eq` or any of `alternatives` under the given `options`.
+    TextEquals {
+        eq: String,
+        #[serde(default)]
+        alternatives: Vec<String>,
+        #[serde(default)]
+        options: TextMatchOptions,
+    },
+    /// Matches if the whole (normalized) response matches the regex `pattern`.
+    TextMatches {
+        pattern: String,
+        #[serde(default)]
+        options: TextMatchOptions,
+    },
+    /// Exactly equal to `num / den`, compared without rounding.
+    RationalEquals {
+        #[serde(with = "rational::bigint_string")]
+        num: BigInt,
+        #[serde(with = "rational::bigint_string")]
+        den: BigInt,
+        #[serde(default)]
+        lowest_terms: bool,
+    },
+    /// A physical quantity, accepted in any compatible units
+    /// as long as it is within `tolerance` of `value` `unit`.
+    QuantityEquals {
+        value: f64,
+        unit: String,
+        tolerance: Tolerance,
+    },
+    /// Accepts any expression numerically equivalent to `eq` over `variables`.
+    /// If `require_simplified` is set, the response may not be
+    /// any longer (in expression tree nodes) than `eq` itself.
+    ExpressionEquals {
+        eq: String,
+        #[serde(default)]
+        variables: Vec<String>,
+        precision: f64,
+        #[serde(default)]
+        require_simplified: bool,
+    },
 }
 
 impl FreeResponseSolution {
@@ -148,16 +842,105 @@ impl FreeResponseSolution {
     pub fn check(&self, response: String) -> bool {
         match self {
             FreeResponseSolution::RealEquals { eq, precision } => {
-                if let Ok(num) = response.parse::<f64>() {
+                if let Ok(num) = response.trim().parse::<f64>() {
                     (num - eq).abs() < *precision
                 } else {
                     false
                 }
             }
             FreeResponseSolution::ImaginaryEquals { eq, precision } => {
-                unimplemented!("Imaginary numbers not yet supported as a solution")
+                if let Ok(num) = Complex::parse(&response) {
+                    num.approx_eq(eq, *precision)
+                } else {
+                    false
+                }
+            }
+            FreeResponseSolution::TextEquals {
+                eq,
+                alternatives,
+                options,
+            } => std::iter::once(eq)
+                .chain(alternatives)
+                .any(|expected| options.matches(expected, &response)),
+            FreeResponseSolution::TextMatches { pattern, options } => {
+                options.matches_pattern(pattern, &response).unwrap_or(false)
             }
-            FreeResponseSolution::TextEquals { eq } => &response == eq,
+            FreeResponseSolution::RationalEquals {
+                num,
+                den,
+                lowest_terms,
+            } => match (Rational::new(num.clone(), den.clone()), Rational::parse(&response)) {
+                (Some(eq), Ok(parsed)) => parsed.value == eq && (!lowest_terms || parsed.lowest_terms),
+                _ => false,
+            },
+            FreeResponseSolution::QuantityEquals {
+                value,
+                unit,
+                tolerance,
+            } => match (Unit::parse(unit), Quantity::parse(&response)) {
+                (Ok(unit), Ok(quantity)) => quantity
+                    .value_in(&unit)
+                    .is_some_and(|actual| tolerance.accepts(*value, actual)),
+                _ => false,
+            },
+            FreeResponseSolution::ExpressionEquals {
+                eq,
+                variables,
+                precision,
+                require_simplified,
+            } => match (Expr::parse(eq), Expr::parse(&response)) {
+                (Ok(eq), Ok(response)) => {
+                    (!require_simplified || response.size() <= eq.size())
+                        && eq.equivalent(&response, variables, *precision)
+                }
+                _ => false,
+            },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::PuzzleKind;
+
+    fn sudoku(size: usize, box_rows: usize, box_cols: usize) -> ProblemContent {
+        ProblemContent::Grid(GridPuzzle {
+            kind: PuzzleKind::Sudoku { box_rows, box_cols },
+            rows: size,
+            cols: size,
+            givens: vec![],
+            constraints: vec![],
+        })
+    }
+
+    #[test]
+    fn malformed_grids_are_rejected() {
+        assert!(sudoku(4, 2, 2).validate().is_ok());
+        assert!(sudoku(100, 10, 10).validate().is_err());
+        assert!(sudoku(4, usize::MAX, 2).validate().is_err());
+        assert!(sudoku(4, 2, 3).validate().is_err());
+    }
+
+    fn template(constraints: &[&str], answer: &str) -> ProblemContent {
+        ProblemContent::Template {
+            variables: vec![TemplateVariable {
+                name: "a".to_string(),
+                values: VariableValues::IntegerRange { start: 1, end: 10 },
+            }],
+            constraints: constraints
+                .iter()
+                .map(|constraint| constraint.to_string())
+                .collect(),
+            answer: answer.to_string(),
+            precision: 0.01,
+        }
+    }
+
+    #[test]
+    fn unusable_templates_are_rejected() {
+        assert!(template(&["a > 5"], "2a").validate().is_ok());
+        assert!(template(&["a > 10"], "2a").validate().is_err());
+        assert!(template(&[], "2b").validate().is_err());
+    }
+}
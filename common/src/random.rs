@@ -0,0 +1,65 @@
+/// A small deterministic pseudo-random generator (SplitMix64).
+/// It is used wherever the server and the client need to
+/// agree on the same "random" values without sharing state.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seeds a generator from arbitrary bytes, e.g. some text.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self::new(hash_bytes(bytes))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a float uniformly distributed in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a float uniformly distributed in `[start, end)`.
+    pub fn range_f64(&mut self, start: f64, end: f64) -> f64 {
+        start + (end - start) * self.next_f64()
+    }
+
+    /// Returns an integer uniformly distributed in `[0, bound)`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Shuffles `items` in place (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// The seed for anything that should vary between users but stay
+/// the same every time a given user sees a given problem.
+pub fn user_problem_seed(user_id: i32, problem_id: i32) -> u64 {
+    let mut bytes = user_id.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&problem_id.to_le_bytes());
+    hash_bytes(&bytes)
+}
+
+/// A stable 64-bit FNV-1a hash, unlike `std`'s hashers
+/// which aren't guaranteed to agree across builds.
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01B3)
+    })
+}
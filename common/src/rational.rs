@@ -0,0 +1,161 @@
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, Zero};
+use std::{fmt, str::FromStr};
+
+/// An exact rational number, always kept in lowest terms
+/// with a positive denominator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rational {
+    num: BigInt,
+    den: BigInt,
+}
+
+/// The result of parsing a user's rational input.
+/// `lowest_terms` records whether the input was *written* in lowest
+/// terms (e.g. `2/6` and `0.5` are not, `1/3` and `3` are).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedRational {
+    pub value: Rational,
+    pub lowest_terms: bool,
+}
+
+impl Rational {
+    /// Creates a reduced rational, or `None` if `den` is zero.
+    pub fn new(num: BigInt, den: BigInt) -> Option<Self> {
+        if den.is_zero() {
+            return None;
+        }
+        let gcd = num.gcd(&den);
+        let (mut num, mut den) = (num / &gcd, den / &gcd);
+        if den.is_negative() {
+            num = -num;
+            den = -den;
+        }
+        Some(Self { num, den })
+    }
+
+    pub fn numerator(&self) -> &BigInt {
+        &self.num
+    }
+
+    pub fn denominator(&self) -> &BigInt {
+        &self.den
+    }
+
+    /// Parses integers (`3`), fractions (`2/6`, `-7/3`), terminating
+    /// decimals (`0.25`) and mixed numbers (`-1 1/2`) exactly.
+    /// returns a user-displayable error message if the input is malformed.
+    pub fn parse(input: &str) -> Result<ParsedRational, String> {
+        let invalid = || format!("\"{}\" is not a valid fraction or decimal", input.trim());
+        let input = input.trim();
+        let (negative, unsigned) = match input.strip_prefix('-') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, input.strip_prefix('+').unwrap_or(input).trim_start()),
+        };
+
+        let parts: Vec<&str> = unsigned.split_whitespace().collect();
+        let parsed = match parts.as_slice() {
+            [whole, fraction] => {
+                let whole = parse_digits(whole).ok_or_else(invalid)?;
+                let (num, den) = parse_fraction(fraction).ok_or_else(invalid)?;
+                if den.is_zero() {
+                    return Err("The denominator cannot be zero".to_string());
+                }
+                if num >= den {
+                    return Err("The fraction in a mixed number must be less than 1".to_string());
+                }
+                let lowest_terms = num.gcd(&den).is_one();
+                let value = Rational::new(whole * &den + num, den).ok_or_else(invalid)?;
+                ParsedRational { value, lowest_terms }
+            }
+            [single] if single.contains('/') => {
+                let (num, den) = parse_fraction(single).ok_or_else(invalid)?;
+                if den.is_zero() {
+                    return Err("The denominator cannot be zero".to_string());
+                }
+                let lowest_terms = num.gcd(&den).is_one();
+                let value = Rational::new(num, den).ok_or_else(invalid)?;
+                ParsedRational { value, lowest_terms }
+            }
+            [single] => {
+                let (whole, fractional) = match single.find('.') {
+                    Some(idx) => (&single[..idx], &single[idx + 1..]),
+                    None => (*single, ""),
+                };
+                if whole.is_empty() && fractional.is_empty() {
+                    return Err(invalid());
+                }
+                let digits = format!("{}{}", whole, fractional);
+                let num = parse_digits(&digits).ok_or_else(invalid)?;
+                let den = num_traits::pow(BigInt::from(10), fractional.len());
+                let value = Rational::new(num, den).ok_or_else(invalid)?;
+                let lowest_terms = value.den.is_one();
+                ParsedRational { value, lowest_terms }
+            }
+            _ => return Err(invalid()),
+        };
+
+        Ok(if negative {
+            ParsedRational {
+                value: Rational {
+                    num: -parsed.value.num,
+                    den: parsed.value.den,
+                },
+                ..parsed
+            }
+        } else {
+            parsed
+        })
+    }
+}
+
+fn parse_digits(digits: &str) -> Option<BigInt> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn parse_fraction(fraction: &str) -> Option<(BigInt, BigInt)> {
+    let mut split = fraction.splitn(2, '/');
+    let num = parse_digits(split.next()?.trim())?;
+    let den = parse_digits(split.next()?.trim())?;
+    Some((num, den))
+}
+
+impl FromStr for Rational {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rational::parse(s).map(|parsed| parsed.value)
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den.is_one() {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+/// Serializes a `BigInt` as a decimal string so stored
+/// solutions stay readable and lossless in JSON.
+pub mod bigint_string {
+    use num_bigint::BigInt;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+        String::deserialize(deserializer)?
+            .trim()
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
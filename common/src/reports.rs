@@ -0,0 +1,111 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportReason {
+    WrongAnswer,
+    Spam,
+    Offensive,
+    Duplicate,
+}
+
+labels!(ReportReason {
+    WrongAnswer => "wrong_answer",
+    Spam => "spam",
+    Offensive => "offensive",
+    Duplicate => "duplicate",
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    Open,
+    /// A moderator is looking into it.
+    Claimed,
+    Resolved,
+}
+
+labels!(ReportStatus {
+    Open => "open",
+    Claimed => "claimed",
+    Resolved => "resolved",
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportOutcome {
+    /// Nothing was wrong.
+    Dismiss,
+    HideProblem,
+    WarnOwner,
+    /// Stops the owner from signing in until an admin lifts it.
+    SuspendOwner,
+}
+
+labels!(ReportOutcome {
+    Dismiss => "dismiss",
+    HideProblem => "hide_problem",
+    WarnOwner => "warn_owner",
+    SuspendOwner => "suspend_owner",
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewReport {
+    pub reason: ReportReason,
+    pub comment: String,
+}
+
+impl NewReport {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.comment.chars().count() > 1000 {
+            return Err("A report's comment must be at most 1000 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub id: i32,
+    pub problem_id: i32,
+    pub reporter_id: i32,
+    pub reason: ReportReason,
+    pub comment: String,
+    pub status: ReportStatus,
+    pub claimed_by: Option<i32>,
+    pub outcome: Option<ReportOutcome>,
+    pub resolution_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// How a moderator closes a report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Resolution {
+    pub outcome: ReportOutcome,
+    pub note: String,
+}
+
+impl Resolution {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.note.chars().count() > 1000 {
+            return Err("A resolution's note must be at most 1000 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// One moderation action, kept so moderators can be held to account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i32,
+    pub actor_id: i32,
+    /// Such as `resolve_report` or `hide_problem`.
+    pub action: String,
+    pub report_id: Option<i32>,
+    pub problem_id: Option<i32>,
+    pub target_user_id: Option<i32>,
+    pub detail: String,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,100 @@
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Controls how leniently a text response is compared to an answer.
+/// Everything is off by default, which makes comparison exact.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct TextMatchOptions {
+    pub case_insensitive: bool,
+    /// Applies NFKC normalization, so e.g. `ﬁ` matches `fi`.
+    pub normalize_unicode: bool,
+    /// Removes accents and other combining marks, so `é` matches `e`.
+    pub strip_diacritics: bool,
+    /// Trims the text and collapses runs of whitespace into single spaces.
+    pub collapse_whitespace: bool,
+    /// Removes everything that isn't a letter, digit, combining mark or whitespace.
+    /// Combining marks stay, since they're part of letters like `à` or `स्`.
+    pub ignore_punctuation: bool,
+    /// How many single-character edits (Levenshtein distance)
+    /// a response may be away from the answer.
+    pub max_edit_distance: usize,
+}
+
+impl TextMatchOptions {
+    /// Ignores case, punctuation, spacing and how characters are encoded,
+    /// but not diacritics, since those often change a word's meaning.
+    pub fn normalized() -> Self {
+        Self {
+            case_insensitive: true,
+            normalize_unicode: true,
+            collapse_whitespace: true,
+            ignore_punctuation: true,
+            ..Self::default()
+        }
+    }
+
+    /// Applies all enabled normalizations to `text`.
+    pub fn normalize(&self, text: &str) -> String {
+        let mut text = if self.normalize_unicode {
+            text.nfkc().collect()
+        } else {
+            text.to_string()
+        };
+        if self.strip_diacritics {
+            text = text.nfd().filter(|c| !is_combining_mark(*c)).nfc().collect();
+        }
+        if self.case_insensitive {
+            text = text.to_lowercase();
+        }
+        if self.ignore_punctuation {
+            text = text
+                .chars()
+                .filter(|c| c.is_alphanumeric() || is_combining_mark(*c) || c.is_whitespace())
+                .collect();
+        }
+        if self.collapse_whitespace {
+            text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        text
+    }
+
+    /// Checks if `response` matches `expected` under these options.
+    pub fn matches(&self, expected: &str, response: &str) -> bool {
+        let expected = self.normalize(expected);
+        let response = self.normalize(response);
+        if self.max_edit_distance == 0 {
+            expected == response
+        } else {
+            levenshtein(&expected, &response) <= self.max_edit_distance
+        }
+    }
+
+    /// Checks if the whole of `response` matches the regex `pattern`
+    /// after normalization. Errors if the pattern is invalid.
+    pub fn matches_pattern(&self, pattern: &str, response: &str) -> Result<bool, String> {
+        let regex = RegexBuilder::new(&format!("^(?:{})$", pattern))
+            .case_insensitive(self.case_insensitive)
+            .build()
+            .map_err(|error| error.to_string())?;
+        Ok(regex.is_match(&self.normalize(response)))
+    }
+}
+
+/// The number of single-character insertions, deletions and
+/// substitutions needed to turn `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + if a_char == *b_char { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,449 @@
+use std::fmt;
+
+/// Exponents of the seven SI base dimensions,
+/// in the order m, kg, s, A, K, mol, cd.
+pub type Dimension = [i32; 7];
+
+const DIMENSIONLESS: Dimension = [0, 0, 0, 0, 0, 0, 0];
+const LENGTH: Dimension = [1, 0, 0, 0, 0, 0, 0];
+const MASS: Dimension = [0, 1, 0, 0, 0, 0, 0];
+const TIME: Dimension = [0, 0, 1, 0, 0, 0, 0];
+const CURRENT: Dimension = [0, 0, 0, 1, 0, 0, 0];
+const TEMPERATURE: Dimension = [0, 0, 0, 0, 1, 0, 0];
+const AMOUNT: Dimension = [0, 0, 0, 0, 0, 1, 0];
+const LUMINOSITY: Dimension = [0, 0, 0, 0, 0, 0, 1];
+const AREA: Dimension = [2, 0, 0, 0, 0, 0, 0];
+const VOLUME: Dimension = [3, 0, 0, 0, 0, 0, 0];
+const FREQUENCY: Dimension = [0, 0, -1, 0, 0, 0, 0];
+const SPEED: Dimension = [1, 0, -1, 0, 0, 0, 0];
+const FORCE: Dimension = [1, 1, -2, 0, 0, 0, 0];
+const ENERGY: Dimension = [2, 1, -2, 0, 0, 0, 0];
+const POWER: Dimension = [2, 1, -3, 0, 0, 0, 0];
+const PRESSURE: Dimension = [-1, 1, -2, 0, 0, 0, 0];
+const CHARGE: Dimension = [0, 0, 1, 1, 0, 0, 0];
+const VOLTAGE: Dimension = [2, 1, -3, -1, 0, 0, 0];
+const RESISTANCE: Dimension = [2, 1, -3, -2, 0, 0, 0];
+
+/// How deeply parentheses may nest in units, so a malicious response
+/// can't overflow the stack while being parsed.
+const MAX_DEPTH: usize = 64;
+
+/// (symbols, factor to SI, offset to SI, dimension, accepts SI prefixes)
+const UNITS: &[(&[&str], f64, f64, Dimension, bool)] = &[
+    // SI base units (the kilogram is prefixed from the gram)
+    (&["m"], 1.0, 0.0, LENGTH, true),
+    (&["g"], 1e-3, 0.0, MASS, true),
+    (&["s", "sec"], 1.0, 0.0, TIME, true),
+    (&["A"], 1.0, 0.0, CURRENT, true),
+    (&["K"], 1.0, 0.0, TEMPERATURE, true),
+    (&["mol"], 1.0, 0.0, AMOUNT, true),
+    (&["cd"], 1.0, 0.0, LUMINOSITY, true),
+    // SI derived units
+    (&["Hz"], 1.0, 0.0, FREQUENCY, true),
+    (&["N"], 1.0, 0.0, FORCE, true),
+    (&["J"], 1.0, 0.0, ENERGY, true),
+    (&["W"], 1.0, 0.0, POWER, true),
+    (&["Pa"], 1.0, 0.0, PRESSURE, true),
+    (&["C"], 1.0, 0.0, CHARGE, true),
+    (&["V"], 1.0, 0.0, VOLTAGE, true),
+    (&["Ω", "ohm"], 1.0, 0.0, RESISTANCE, true),
+    (&["L", "l"], 1e-3, 0.0, VOLUME, true),
+    (&["eV"], 1.602_176_634e-19, 0.0, ENERGY, true),
+    (&["rad"], 1.0, 0.0, DIMENSIONLESS, false),
+    // Other common metric units
+    (&["min"], 60.0, 0.0, TIME, false),
+    (&["h", "hr"], 3600.0, 0.0, TIME, false),
+    (&["d", "day"], 86400.0, 0.0, TIME, false),
+    (&["t"], 1e3, 0.0, MASS, false),
+    (&["ha"], 1e4, 0.0, AREA, false),
+    (&["bar"], 1e5, 0.0, PRESSURE, true),
+    (&["atm"], 101_325.0, 0.0, PRESSURE, false),
+    (&["cal"], 4.184, 0.0, ENERGY, true),
+    (&["deg", "°"], std::f64::consts::PI / 180.0, 0.0, DIMENSIONLESS, false),
+    (&["degC", "°C"], 1.0, 273.15, TEMPERATURE, false),
+    // Imperial and US customary units
+    (&["in"], 0.0254, 0.0, LENGTH, false),
+    (&["ft"], 0.3048, 0.0, LENGTH, false),
+    (&["yd"], 0.9144, 0.0, LENGTH, false),
+    (&["mi"], 1609.344, 0.0, LENGTH, false),
+    (&["oz"], 0.028_349_523_125, 0.0, MASS, false),
+    (&["lb", "lbs"], 0.453_592_37, 0.0, MASS, false),
+    (&["lbf"], 4.448_221_615_260_5, 0.0, FORCE, false),
+    (&["mph"], 0.44704, 0.0, SPEED, false),
+    (&["gal"], 3.785_411_784e-3, 0.0, VOLUME, false),
+    (&["psi"], 6_894.757_293_168, 0.0, PRESSURE, false),
+    (&["hp"], 745.699_871_582_270_2, 0.0, POWER, false),
+    (&["degF", "°F"], 5.0 / 9.0, 459.67 * 5.0 / 9.0, TEMPERATURE, false),
+];
+
+const PREFIXES: &[(&str, f64)] = &[
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("da", 1e1),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("µ", 1e-6),
+    ("μ", 1e-6),
+    ("u", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+];
+
+/// A (possibly compound) unit, e.g. `km/h` or `kg*m/s^2`,
+/// stored as its conversion to SI base units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    pub factor: f64,
+    /// Only non-zero for a lone offset temperature unit like `°C`.
+    pub offset: f64,
+    pub dimension: Dimension,
+}
+
+impl Unit {
+    pub fn dimensionless() -> Self {
+        Self {
+            factor: 1.0,
+            offset: 0.0,
+            dimension: DIMENSIONLESS,
+        }
+    }
+
+    /// Parses a unit expression. Factors may be separated by `*`, `·` or
+    /// spaces, divided with `/`, raised to integer powers with `^`
+    /// (or written like `s2`), and grouped with parentheses.
+    /// returns a user-displayable error message if the input is malformed.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(Unit::dimensionless());
+        }
+        if let Some(unit) = lookup(input) {
+            // Offsets only make sense for a temperature unit on its own
+            return Ok(unit);
+        }
+        let chars: Vec<char> = input.chars().collect();
+        let mut pos = 0;
+        let unit = parse_product(&chars, &mut pos, 0)?;
+        if pos < chars.len() {
+            return Err(format!("Unexpected \"{}\" in units", chars[pos]));
+        }
+        Ok(unit)
+    }
+
+    pub fn is_compatible(&self, other: &Unit) -> bool {
+        self.dimension == other.dimension
+    }
+
+    pub fn to_si(&self, value: f64) -> f64 {
+        value * self.factor + self.offset
+    }
+
+    pub fn from_si(&self, value: f64) -> f64 {
+        (value - self.offset) / self.factor
+    }
+
+    fn pow(&self, exponent: i32) -> Result<Self, String> {
+        let mut dimension = self.dimension;
+        for dim in dimension.iter_mut() {
+            *dim = dim.checked_mul(exponent).ok_or_else(too_large_power)?;
+        }
+        Ok(Self {
+            factor: self.factor.powi(exponent),
+            offset: 0.0,
+            dimension,
+        })
+    }
+
+    fn mul(&self, other: &Unit) -> Result<Self, String> {
+        let mut dimension = self.dimension;
+        for (dim, other) in dimension.iter_mut().zip(other.dimension.iter()) {
+            *dim = dim.checked_add(*other).ok_or_else(too_large_power)?;
+        }
+        Ok(Self {
+            factor: self.factor * other.factor,
+            offset: 0.0,
+            dimension,
+        })
+    }
+}
+
+fn too_large_power() -> String {
+    "Units have too large a power".to_string()
+}
+
+fn lookup(symbol: &str) -> Option<Unit> {
+    let find = |symbol: &str| {
+        UNITS
+            .iter()
+            .find(|(symbols, ..)| symbols.contains(&symbol))
+            .map(|(_, factor, offset, dimension, prefixable)| {
+                (
+                    Unit {
+                        factor: *factor,
+                        offset: *offset,
+                        dimension: *dimension,
+                    },
+                    *prefixable,
+                )
+            })
+    };
+    if let Some((unit, _)) = find(symbol) {
+        return Some(unit);
+    }
+    PREFIXES.iter().find_map(|(prefix, scale)| {
+        let rest = symbol.strip_prefix(prefix)?;
+        match find(rest) {
+            Some((unit, true)) => Some(Unit {
+                factor: unit.factor * scale,
+                ..unit
+            }),
+            _ => None,
+        }
+    })
+}
+
+fn skip_separators(chars: &[char], pos: &mut usize) -> bool {
+    let mut skipped = false;
+    while *pos < chars.len() && matches!(chars[*pos], ' ' | '*' | '·' | '⋅') {
+        *pos += 1;
+        skipped = true;
+    }
+    skipped
+}
+
+/// Parses factors up to the end or a closing parenthesis, `depth`
+/// parentheses deep.
+fn parse_product(chars: &[char], pos: &mut usize, depth: usize) -> Result<Unit, String> {
+    if depth > MAX_DEPTH {
+        return Err("Units are nested too deeply".to_string());
+    }
+    let mut unit = Unit::dimensionless();
+    let mut divide = false;
+    loop {
+        skip_separators(chars, pos);
+        if *pos >= chars.len() || chars[*pos] == ')' {
+            if divide {
+                return Err("Expected a unit after \"/\"".to_string());
+            }
+            return Ok(unit);
+        }
+        let factor = parse_factor(chars, pos, depth)?;
+        unit = unit.mul(&if divide { factor.pow(-1)? } else { factor })?;
+        divide = false;
+        skip_separators(chars, pos);
+        if *pos < chars.len() && chars[*pos] == '/' {
+            *pos += 1;
+            divide = true;
+        }
+    }
+}
+
+fn parse_factor(chars: &[char], pos: &mut usize, depth: usize) -> Result<Unit, String> {
+    let base = if chars[*pos] == '(' {
+        *pos += 1;
+        let inner = parse_product(chars, pos, depth + 1)?;
+        if *pos >= chars.len() || chars[*pos] != ')' {
+            return Err("Missing closing parenthesis in units".to_string());
+        }
+        *pos += 1;
+        inner
+    } else {
+        let start = *pos;
+        while *pos < chars.len() && (chars[*pos].is_alphabetic() || matches!(chars[*pos], 'Ω' | '°'))
+        {
+            *pos += 1;
+        }
+        if start == *pos {
+            return Err(format!("Unexpected \"{}\" in units", chars[*pos]));
+        }
+        let symbol: String = chars[start..*pos].iter().collect();
+        let unit = lookup(&symbol).ok_or_else(|| format!("Unknown unit \"{}\"", symbol))?;
+        if unit.offset != 0.0 {
+            return Err(format!("\"{}\" can't be combined with other units", symbol));
+        }
+        unit
+    };
+
+    let explicit_power = *pos < chars.len() && chars[*pos] == '^';
+    if explicit_power {
+        *pos += 1;
+    }
+    let start = *pos;
+    if *pos < chars.len() && chars[*pos] == '-' {
+        *pos += 1;
+    }
+    while *pos < chars.len() && chars[*pos].is_ascii_digit() {
+        *pos += 1;
+    }
+    let exponent: String = chars[start..*pos].iter().collect();
+    if exponent.is_empty() {
+        if explicit_power {
+            return Err("Expected a power after \"^\" in units".to_string());
+        }
+        return Ok(base);
+    }
+    let exponent = exponent
+        .parse::<i32>()
+        .map_err(|_| format!("\"{}\" is not a valid power", exponent))?;
+    base.pow(exponent)
+}
+
+/// A measured value with units, e.g. `9.8 m/s^2` or `3.00e8`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+    /// Significant figures in the written value, not counting
+    /// ambiguous trailing zeros in integers like `100`.
+    pub sig_figs: usize,
+}
+
+impl Quantity {
+    /// Parses a number followed by optional units. The number may use
+    /// `e` notation or be written like `3.00 x 10^8`.
+    /// returns a user-displayable error message if the input is malformed.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let input = input.trim();
+        let chars: Vec<char> = input.chars().collect();
+        let mut pos = 0;
+        if pos < chars.len() && (chars[pos] == '-' || chars[pos] == '+') {
+            pos += 1;
+        }
+        let mantissa_start = pos;
+        while pos < chars.len() && (chars[pos].is_ascii_digit() || chars[pos] == '.') {
+            pos += 1;
+        }
+        let mantissa: String = chars[mantissa_start..pos].iter().collect();
+        if !mantissa.chars().any(|c| c.is_ascii_digit()) {
+            return Err("Input must start with a number".to_string());
+        }
+        let mut number: String = chars[..pos].iter().collect();
+
+        // `e` notation, taking care not to swallow units like `eV`
+        if pos < chars.len() && (chars[pos] == 'e' || chars[pos] == 'E') {
+            let mut end = pos + 1;
+            if end < chars.len() && (chars[end] == '-' || chars[end] == '+') {
+                end += 1;
+            }
+            if end < chars.len() && chars[end].is_ascii_digit() {
+                while end < chars.len() && chars[end].is_ascii_digit() {
+                    end += 1;
+                }
+                number.extend(chars[pos..end].iter());
+                pos = end;
+            }
+        }
+        let mut value = number
+            .parse::<f64>()
+            .map_err(|_| format!("\"{}\" is not a valid number", number))?;
+
+        // `x 10^n` notation
+        let rest: String = chars[pos..].iter().collect();
+        let trimmed = rest.trim_start();
+        let mut units = rest.as_str();
+        for times in &["x", "×", "*", "·"] {
+            if let Some(power) = trimmed
+                .strip_prefix(times)
+                .and_then(|after| after.trim_start().strip_prefix("10^"))
+            {
+                let digits = power
+                    .char_indices()
+                    .find(|(i, c)| !(c.is_ascii_digit() || (*i == 0 && (*c == '-' || *c == '+'))))
+                    .map_or(power.len(), |(i, _)| i);
+                let exponent = power[..digits]
+                    .parse::<i32>()
+                    .map_err(|_| "Expected a whole number power of 10".to_string())?;
+                value *= 10f64.powi(exponent);
+                units = &power[digits..];
+                break;
+            }
+        }
+
+        Ok(Self {
+            value,
+            unit: Unit::parse(units)?,
+            sig_figs: count_sig_figs(&mantissa),
+        })
+    }
+
+    /// The value of this quantity expressed in `unit`,
+    /// or `None` if the units are incompatible.
+    pub fn value_in(&self, unit: &Unit) -> Option<f64> {
+        if self.unit.is_compatible(unit) {
+            Some(unit.from_si(self.unit.to_si(self.value)))
+        } else {
+            None
+        }
+    }
+}
+
+fn count_sig_figs(mantissa: &str) -> usize {
+    let digits: String = mantissa.chars().filter(|c| c.is_ascii_digit()).collect();
+    let significant = digits.trim_start_matches('0');
+    if significant.is_empty() {
+        // Zero itself, e.g. `0.00` has as many figures as decimals written
+        return mantissa.split('.').nth(1).map_or(1, |decimals| decimals.len().max(1));
+    }
+    if mantissa.contains('.') {
+        significant.len()
+    } else {
+        significant.trim_end_matches('0').len()
+    }
+}
+
+/// How close a response has to be to the expected value.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Tolerance {
+    /// A fraction of the expected value, e.g. `0.01` for 1%.
+    Relative(f64),
+    /// An amount in the units of the expected value.
+    Absolute(f64),
+}
+
+impl Tolerance {
+    pub fn accepts(&self, expected: f64, actual: f64) -> bool {
+        let difference = (actual - expected).abs();
+        match self {
+            Tolerance::Relative(fraction) => difference <= fraction * expected.abs(),
+            Tolerance::Absolute(amount) => difference <= *amount,
+        }
+    }
+}
+
+impl fmt::Display for Tolerance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tolerance::Relative(fraction) => write!(f, "±{}%", fraction * 100.0),
+            Tolerance::Absolute(amount) => write!(f, "±{}", amount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deeply_nested_units_are_rejected() {
+        let units = format!("5 {}m{}", "(".repeat(3000), ")".repeat(3000));
+        assert!(Quantity::parse(&units).is_err());
+        assert!(Quantity::parse("5 ((m))/s").is_ok());
+    }
+
+    #[test]
+    fn overflowing_powers_are_rejected() {
+        assert!(Quantity::parse("5 (m^99999)^99999").is_err());
+        assert!(Quantity::parse("5 m^2147483647 m").is_err());
+        assert!(Quantity::parse("5 (m^2)^3").is_ok());
+    }
+
+    #[test]
+    fn trailing_division_is_rejected() {
+        assert!(Quantity::parse("5 m/").is_err());
+        assert!(Quantity::parse("5 (m/)s").is_err());
+    }
+}
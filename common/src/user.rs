@@ -1,4 +1,45 @@
 use serde::{Serialize, Deserialize};
+use std::fmt;
+
+/// What a user is trusted with. Each role has every power of the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    pub fn can(self, permission: Permission) -> bool {
+        self >= permission.required_role()
+    }
+}
+
+labels!(Role {
+    User => "user",
+    Moderator => "moderator",
+    Admin => "admin",
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Hiding and unhiding problems, and editing their tags.
+    ModerateProblems,
+    HandleReports,
+    ManageTopics,
+    ManageUsers,
+}
+
+impl Permission {
+    pub fn required_role(self) -> Role {
+        match self {
+            Permission::ModerateProblems | Permission::HandleReports => Role::Moderator,
+            Permission::ManageTopics | Permission::ManageUsers => Role::Admin,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct User {
@@ -6,4 +47,32 @@ pub struct User {
     pub name: String,
     pub pass: String,
     pub recommended_ids: Vec<i32>,
-}
\ No newline at end of file
+    #[serde(default)]
+    pub role: Role,
+}
+
+impl User {
+    pub fn can(&self, permission: Permission) -> bool {
+        self.role.can(permission)
+    }
+}
+
+/// What the client sends to create an account.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Signup {
+    pub name: String,
+    pub pass: String,
+    /// Solves the session's signup challenge, if the server asks for one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<u64>,
+}
+
+impl fmt::Debug for Signup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Signup")
+            .field("name", &self.name)
+            .field("pass", &"[redacted]")
+            .field("nonce", &self.nonce)
+            .finish()
+    }
+}
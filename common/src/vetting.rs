@@ -0,0 +1,110 @@
+//! Wrong free-response answers that a problem's owner can vet,
+//! either accepting them as solutions or rejecting them with a note.
+
+use crate::{problems::FreeResponseSolution, text::TextMatchOptions};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Longer answers aren't queued, so the queue can't be flooded with essays.
+pub const MAX_ANSWER_CHARS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VettingStatus {
+    /// Waiting for the owner.
+    Pending,
+    /// Added to the problem's solutions.
+    Accepted,
+    Rejected,
+}
+
+labels!(VettingStatus {
+    Pending => "pending",
+    Accepted => "accepted",
+    Rejected => "rejected",
+});
+
+/// Text answers with digits in them keep their punctuation, since it's what
+/// tells `1/2`, `2,5` and `12` apart.
+fn text_options(answer: &str) -> TextMatchOptions {
+    TextMatchOptions {
+        ignore_punctuation: !answer.chars().any(|c| c.is_ascii_digit()),
+        ..TextMatchOptions::normalized()
+    }
+}
+
+fn parse_number(answer: &str) -> Option<f64> {
+    answer.trim().parse::<f64>().ok().filter(|num| num.is_finite())
+}
+
+/// The key wrong answers are grouped under. Numbers are compared by value,
+/// so `2.50` and `2.5` are the same answer, and anything else as text,
+/// ignoring case and spacing, and punctuation unless it has digits in it.
+/// Keys are only for grouping, so are never turned back into solutions.
+pub fn normalize_answer(response: &str) -> String {
+    match parse_number(response) {
+        Some(num) => num.to_string(),
+        None => text_options(response.trim()).normalize(response),
+    }
+}
+
+/// The solution that accepts `example`, a response as it was typed, and
+/// every other response with the same [`normalize_answer`] key.
+pub fn solution_for(example: &str) -> FreeResponseSolution {
+    match parse_number(example) {
+        Some(num) => FreeResponseSolution::RealEquals {
+            eq: num,
+            // Enough to absorb rounding, but not to accept any other answer
+            precision: (num.abs() * 1e-9).max(1e-9),
+        },
+        None => FreeResponseSolution::TextEquals {
+            eq: example.trim().to_string(),
+            alternatives: vec![],
+            options: text_options(example.trim()),
+        },
+    }
+}
+
+/// A wrong answer to a free-response problem, with how many people gave it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerCandidate {
+    pub id: i32,
+    pub problem_id: i32,
+    /// As normalized by [`normalize_answer`].
+    pub answer: String,
+    /// The first response given, trimmed, which is what accepting adds.
+    pub example: String,
+    pub submissions: i64,
+    pub status: VettingStatus,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Why an owner rejected an answer, shown to everyone who gave it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rejection {
+    pub note: String,
+}
+
+impl Rejection {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.note.trim().is_empty() {
+            return Err("Please say why the answer is wrong".to_string());
+        }
+        if self.note.chars().count() > 1000 {
+            return Err("A rejection's note must be at most 1000 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// How the owner vetted one of the signed in user's answers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VettedAnswer {
+    pub response: String,
+    pub status: VettingStatus,
+    pub note: Option<String>,
+    /// Whether the response is right now that the answer has been vetted.
+    pub correct: bool,
+    pub submitted_at: DateTime<Utc>,
+}
@@ -0,0 +1,18 @@
+use std::fs;
+
+fn main() {
+    // Migrations are embedded in the binary, so rebuild when they change
+    println!("cargo:rerun-if-changed=migrations");
+    let latest = fs::read_dir("migrations")
+        .expect("Expected a migrations directory")
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            // Like diesel, take the version to be everything before the first underscore
+            let name = entry.file_name().into_string().ok()?;
+            name.split('_').next().map(str::to_string)
+        })
+        .max()
+        .expect("Expected at least one migration");
+    println!("cargo:rustc-env=LATEST_MIGRATION={}", latest);
+}
@@ -1,11 +1,12 @@
-CREATE TABLE users (
+-- Databases set up before migrations were tracked already have these tables
+CREATE TABLE IF NOT EXISTS users (
     id Serial PRIMARY KEY,
     name VarChar NOT NULL,
     pass VarChar NOT NULL,
     recommended_ids Int4[] NOT NULL DEFAULT ARRAY[]::Int4[]
 );
 
-CREATE TABLE problems (
+CREATE TABLE IF NOT EXISTS problems (
     id Serial PRIMARY KEY,
     owner_id Int4 NOT NULL,
     p_type VarChar NOT NULL,
@@ -0,0 +1,17 @@
+ALTER TABLE problems ADD COLUMN topic VarChar;
+-- Subtopics fall back to the JSON value of their root topic
+WITH RECURSIVE roots (id, slug) AS (
+    SELECT id, slug FROM topics WHERE parent_id IS NULL
+    UNION ALL
+    SELECT topics.id, roots.slug FROM topics JOIN roots ON topics.parent_id = roots.id
+)
+UPDATE problems SET topic = CASE roots.slug
+    WHEN 'math' THEN '"Math"'
+    WHEN 'linguistics' THEN '"Trivia"'
+    ELSE '"Logic"'
+END
+FROM roots
+WHERE roots.id = problems.topic_id;
+ALTER TABLE problems ALTER COLUMN topic SET NOT NULL;
+ALTER TABLE problems DROP COLUMN topic_id;
+DROP TABLE topics;
@@ -0,0 +1,25 @@
+CREATE TABLE topics (
+    id Serial PRIMARY KEY,
+    slug VarChar NOT NULL UNIQUE,
+    name VarChar NOT NULL,
+    description VarChar NOT NULL DEFAULT '',
+    parent_id Int4 REFERENCES topics (id),
+    icon VarChar
+);
+
+INSERT INTO topics (slug, name, description) VALUES
+    ('math', 'Math', 'Problems solved with numbers, algebra, geometry and proofs'),
+    ('linguistics', 'Linguistics', 'Problems about languages and how they work'),
+    ('logic', 'Logic', 'Puzzles solved by careful reasoning');
+
+-- Problems used to store their topic as a JSON string such as "Math"
+ALTER TABLE problems ADD COLUMN topic_id Int4 REFERENCES topics (id);
+UPDATE problems SET topic_id = topics.id
+FROM topics
+WHERE topics.slug = CASE problems.topic
+    WHEN '"Math"' THEN 'math'
+    WHEN '"Trivia"' THEN 'linguistics'
+    WHEN '"Logic"' THEN 'logic'
+END;
+ALTER TABLE problems ALTER COLUMN topic_id SET NOT NULL;
+ALTER TABLE problems DROP COLUMN topic;
@@ -0,0 +1,6 @@
+ALTER TABLE problems DROP COLUMN content_version;
+ALTER TABLE problems
+    ALTER COLUMN data TYPE VarChar USING data::text;
+ALTER TABLE problems
+    ALTER COLUMN p_type TYPE VarChar USING '"' || p_type::text || '"';
+DROP TYPE problem_type;
@@ -0,0 +1,22 @@
+CREATE TYPE problem_type AS ENUM (
+    'FreeResponse',
+    'MultipleChoice',
+    'Checklist',
+    'Ordering',
+    'Matching',
+    'MultiPart',
+    'Template',
+    'Cloze',
+    'Grid',
+    'Translation'
+);
+
+-- p_type used to hold a JSON string such as "MultipleChoice"
+ALTER TABLE problems
+    ALTER COLUMN p_type TYPE problem_type USING trim(BOTH '"' FROM p_type)::problem_type;
+ALTER TABLE problems
+    ALTER COLUMN data TYPE JsonB USING data::jsonb;
+
+-- Everything stored so far is in the first content format
+ALTER TABLE problems ADD COLUMN content_version Int4 NOT NULL DEFAULT 1;
+ALTER TABLE problems ALTER COLUMN content_version DROP DEFAULT;
@@ -0,0 +1,15 @@
+-- Token buckets for the Postgres rate limit store, keyed by route and client
+CREATE TABLE rate_limit_buckets (
+    key VarChar PRIMARY KEY,
+    tokens Float8 NOT NULL,
+    -- Whether the last request took a token
+    allowed Bool NOT NULL,
+    updated_at Timestamptz NOT NULL
+);
+
+-- Failed logins by user name, for exponential backoff
+CREATE TABLE login_failures (
+    name VarChar PRIMARY KEY,
+    failures Int4 NOT NULL,
+    blocked_until Timestamptz NOT NULL
+);
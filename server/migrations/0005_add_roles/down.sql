@@ -0,0 +1,3 @@
+ALTER TABLE problems DROP COLUMN hidden;
+ALTER TABLE users DROP COLUMN role;
+DROP TYPE user_role;
@@ -0,0 +1,5 @@
+CREATE TYPE user_role AS ENUM ('user', 'moderator', 'admin');
+ALTER TABLE users ADD COLUMN role user_role NOT NULL DEFAULT 'user';
+
+-- Hidden problems are only shown to moderators
+ALTER TABLE problems ADD COLUMN hidden Bool NOT NULL DEFAULT false;
@@ -0,0 +1,7 @@
+ALTER TABLE users DROP COLUMN suspended;
+ALTER TABLE users DROP COLUMN warnings;
+DROP TABLE audit_log;
+DROP TABLE reports;
+DROP TYPE report_outcome;
+DROP TYPE report_status;
+DROP TYPE report_reason;
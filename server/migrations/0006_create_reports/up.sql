@@ -0,0 +1,33 @@
+CREATE TYPE report_reason AS ENUM ('wrong_answer', 'spam', 'offensive', 'duplicate');
+CREATE TYPE report_status AS ENUM ('open', 'claimed', 'resolved');
+CREATE TYPE report_outcome AS ENUM ('dismiss', 'hide_problem', 'warn_owner', 'suspend_owner');
+
+CREATE TABLE reports (
+    id Serial PRIMARY KEY,
+    problem_id Int4 NOT NULL REFERENCES problems (id) ON DELETE CASCADE,
+    reporter_id Int4 NOT NULL,
+    reason report_reason NOT NULL,
+    comment VarChar NOT NULL,
+    status report_status NOT NULL DEFAULT 'open',
+    claimed_by Int4,
+    outcome report_outcome,
+    resolution_note VarChar,
+    created_at Timestamptz NOT NULL DEFAULT now(),
+    resolved_at Timestamptz
+);
+CREATE INDEX reports_status ON reports (status, created_at);
+
+-- Kept even after the users and problems involved are deleted
+CREATE TABLE audit_log (
+    id Serial PRIMARY KEY,
+    actor_id Int4 NOT NULL,
+    action VarChar NOT NULL,
+    report_id Int4,
+    problem_id Int4,
+    target_user_id Int4,
+    detail VarChar NOT NULL DEFAULT '',
+    created_at Timestamptz NOT NULL DEFAULT now()
+);
+
+ALTER TABLE users ADD COLUMN warnings Int4 NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN suspended Bool NOT NULL DEFAULT false;
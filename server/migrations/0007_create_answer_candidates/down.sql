@@ -0,0 +1,3 @@
+DROP TABLE candidate_submissions;
+DROP TABLE answer_candidates;
+DROP TYPE vetting_status;
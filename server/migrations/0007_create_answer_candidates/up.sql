@@ -0,0 +1,28 @@
+CREATE TYPE vetting_status AS ENUM ('pending', 'accepted', 'rejected');
+
+-- Wrong answers to free-response problems, grouped by their normalized text
+CREATE TABLE answer_candidates (
+    id Serial PRIMARY KEY,
+    problem_id Int4 NOT NULL REFERENCES problems (id) ON DELETE CASCADE,
+    answer VarChar NOT NULL,
+    -- The first response given, trimmed, which is what accepting adds
+    example VarChar NOT NULL,
+    status vetting_status NOT NULL DEFAULT 'pending',
+    note VarChar,
+    created_at Timestamptz NOT NULL DEFAULT now(),
+    vetted_at Timestamptz,
+    UNIQUE (problem_id, answer)
+);
+CREATE INDEX answer_candidates_status ON answer_candidates (status);
+
+-- Each user's latest response under each candidate, so repeating
+-- an answer doesn't count twice
+CREATE TABLE candidate_submissions (
+    candidate_id Int4 NOT NULL REFERENCES answer_candidates (id) ON DELETE CASCADE,
+    user_id Int4 NOT NULL,
+    response VarChar NOT NULL,
+    correct Bool NOT NULL DEFAULT false,
+    submitted_at Timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (candidate_id, user_id)
+);
+CREATE INDEX candidate_submissions_user ON candidate_submissions (user_id);
@@ -1,5 +1,8 @@
 mod account;
 mod problems;
+mod reports;
+mod topics;
+mod vetting;
 
 use actix_web::web;
 
@@ -7,6 +10,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/")
             .service(web::scope("/account").configure(account::config))
-            .service(web::scope("/problems").configure(problems::config)),
+            .service(web::scope("/problems").configure(problems::config))
+            .service(web::scope("/reports").configure(reports::config))
+            .service(web::scope("/topics").configure(topics::config))
+            .service(web::scope("/vetting").configure(vetting::config)),
     );
 }
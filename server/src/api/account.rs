@@ -1,17 +1,30 @@
-use crate::validate;
 use actix_session::Session;
-use actix_web::{http, web, Error, HttpResponse, Responder};
+use actix_web::{http, web, Error, HttpRequest, HttpResponse, Responder};
+use akshar_owo::{
+    config::Config,
+    metrics,
+    rate_limit::{self, RateLimiter},
+};
+use common::{
+    pow::Challenge,
+    user::{Permission, Role, Signup},
+};
 use diesel::prelude::*;
+use tracing::{error, info};
 
-use crate::{database::models, DbPool};
+use crate::{authorize, database::models, validate, DbPool};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/")
             .route("", web::get().to(get_from_session))
+            .route("/challenge", web::get().to(challenge))
             .route("/create", web::post().to(create))
             .route("/login", web::post().to(login))
-            .route("/{id}", web::get().to(get)),
+            .route("/{id}", web::get().to(get))
+            .route("/{id}", web::delete().to(delete))
+            .route("/{id}/role", web::put().to(set_role))
+            .route("/{id}/suspended", web::put().to(set_suspended)),
     );
 }
 
@@ -37,10 +50,10 @@ async fn get(
     }
     let id = req.into_inner();
     let conn = pool.get().expect("couldn't get db connection from pool");
-    let resp = web::block(move || models::User::get_by_id(id, &conn))
+    let resp = metrics::block(move || models::User::get_by_id(id, &conn))
         .await
         .map_err(|e| {
-            eprintln!("{}", e);
+            error!("{}", e);
             HttpResponse::InternalServerError().finish()
         })?;
     Ok(if let Some(user) = resp {
@@ -52,50 +65,228 @@ async fn get(
     })
 }
 
+/// Hands out the proof of work a signup from this session has to include.
+async fn challenge(session: Session, config: web::Data<Config>) -> Result<HttpResponse, Error> {
+    let challenge = Challenge {
+        challenge: uuid::Uuid::new_v4().to_string(),
+        difficulty: config.rate_limit.signup_difficulty,
+    };
+    session.set("signup_challenge", &challenge)?;
+    Ok(HttpResponse::Ok().json(challenge))
+}
+
 async fn create(
     session: Session,
     pool: web::Data<DbPool>,
-    req: web::Json<models::NewUser>,
+    config: web::Data<Config>,
+    req: web::Json<Signup>,
 ) -> Result<impl Responder, Error> {
+    if config.rate_limit.signup_difficulty > 0 {
+        let challenge = session.get::<Challenge>("signup_challenge")?;
+        // A challenge is good for one attempt, so a solved one can't be replayed
+        session.remove("signup_challenge");
+        let solved = match (challenge, req.nonce) {
+            (Some(challenge), Some(nonce)) => {
+                challenge.difficulty >= config.rate_limit.signup_difficulty
+                    && challenge.is_solved_by(&req.name, nonce)
+            }
+            _ => false,
+        };
+        if !solved {
+            return Ok(HttpResponse::Forbidden().body("Missing or incorrect proof of work"));
+        }
+    }
+    let req = models::NewUser {
+        name: req.name.clone(),
+        pass: req.pass.clone(),
+    };
     let conn = pool.get().expect("couldn't get db connection from pool");
-    let new_user = web::block(
+    let new_user = metrics::block(
         move || -> Result<Option<models::User>, diesel::result::Error> {
             if models::User::get_by_name(req.name.clone(), &conn)?.is_some() {
                 return Ok(None);
             }
-            let req: models::NewUser = req.into_inner().into();
             req.insert(&conn).optional()
         },
     )
     .await
     .map_err(|e| {
-        eprintln!("{}", e);
+        error!("{}", e);
         HttpResponse::InternalServerError().finish()
     })?;
     Ok(if let Some(new_user) = new_user {
         let new_user: models::SessionUser = new_user.into();
         session.set("user", new_user)?;
-        HttpResponse::Ok()
+        HttpResponse::Ok().finish()
     } else {
-        HttpResponse::NotAcceptable()
+        HttpResponse::NotAcceptable().finish()
     })
 }
 
 async fn login(
     session: Session,
     pool: web::Data<DbPool>,
+    limiter: web::Data<RateLimiter>,
+    http_req: HttpRequest,
     req: web::Json<models::NewUser>,
 ) -> Result<impl Responder, Error> {
+    let login_key = limiter.login_key(&req.name, &http_req);
     let conn = pool.get().expect("couldn't get db connection from pool");
-    let user = web::block(move || req.get(&conn)).await.map_err(|e| {
-        eprintln!("{}", e);
-        HttpResponse::InternalServerError()
+    let resp = metrics::block(
+        move || -> Result<Result<Option<models::User>, std::time::Duration>, String> {
+            // Checked before the password, so guessing gets no faster by ignoring the backoff
+            if let Some(wait) = limiter.login_blocked_for(&login_key)? {
+                return Ok(Err(wait));
+            }
+            let user = req.get(&conn).map_err(|e| e.to_string())?;
+            if user.is_some() {
+                limiter.login_succeeded(&login_key)?;
+            } else {
+                limiter.login_failed(&login_key)?;
+            }
+            Ok(Ok(user))
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
     })?;
-    Ok(if let Some(user) = user {
-        let user: models::SessionUser = user.into();
-        session.set("user", user)?;
-        HttpResponse::Ok()
+    Ok(match resp {
+        Ok(Some(user)) if user.suspended => {
+            info!(user_id = user.id, "login refused for suspended account");
+            HttpResponse::Forbidden().body("Account suspended")
+        }
+        Ok(Some(user)) => {
+            let user: models::SessionUser = user.into();
+            session.set("user", user)?;
+            HttpResponse::Ok().finish()
+        }
+        Ok(None) => HttpResponse::NotAcceptable().finish(),
+        Err(wait) => {
+            info!("login refused during backoff");
+            HttpResponse::TooManyRequests()
+                .header(http::header::RETRY_AFTER, rate_limit::retry_after(wait))
+                .finish()
+        }
+    })
+}
+
+async fn set_role(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    req: web::Json<Role>,
+) -> Result<impl Responder, Error> {
+    if !authorize(&session, pool.clone(), Permission::ManageUsers).await? {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    let actor_id = session.get::<models::SessionUser>("user")?.unwrap().id;
+    let (id, role) = (id.into_inner(), req.into_inner());
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let changed = metrics::block(move || {
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let changed = models::User::set_role_by_id(id, role, &conn)?;
+            if changed > 0 {
+                models::NewAuditEntry {
+                    target_user_id: Some(id),
+                    detail: role.to_string(),
+                    ..models::NewAuditEntry::new(actor_id, "set_role")
+                }
+                .insert(&conn)?;
+            }
+            Ok(changed)
+        })
+    })
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(if changed == 0 {
+        HttpResponse::NotFound().body("Could not find user")
+    } else {
+        info!(user_id = id, %role, "changed user role");
+        HttpResponse::Ok().finish()
+    })
+}
+
+async fn set_suspended(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    req: web::Json<bool>,
+) -> Result<impl Responder, Error> {
+    if !authorize(&session, pool.clone(), Permission::ManageUsers).await? {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    let actor_id = session.get::<models::SessionUser>("user")?.unwrap().id;
+    let (id, suspended) = (id.into_inner(), req.into_inner());
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let changed = metrics::block(move || {
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let changed = models::User::set_suspended(id, suspended, &conn)?;
+            if changed > 0 {
+                let action = if suspended {
+                    "suspend_user"
+                } else {
+                    "unsuspend_user"
+                };
+                models::NewAuditEntry {
+                    target_user_id: Some(id),
+                    ..models::NewAuditEntry::new(actor_id, action)
+                }
+                .insert(&conn)?;
+            }
+            Ok(changed)
+        })
+    })
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(if changed == 0 {
+        HttpResponse::NotFound().body("Could not find user")
+    } else {
+        info!(user_id = id, suspended, "changed user suspension");
+        HttpResponse::Ok().finish()
+    })
+}
+
+async fn delete(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    if !authorize(&session, pool.clone(), Permission::ManageUsers).await? {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    let actor_id = session.get::<models::SessionUser>("user")?.unwrap().id;
+    let id = id.into_inner();
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let deleted = metrics::block(move || {
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let deleted = models::User::delete_by_id(id, &conn)?;
+            if deleted > 0 {
+                models::NewAuditEntry {
+                    target_user_id: Some(id),
+                    ..models::NewAuditEntry::new(actor_id, "delete_user")
+                }
+                .insert(&conn)?;
+            }
+            Ok(deleted)
+        })
+    })
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(if deleted == 0 {
+        HttpResponse::NotFound().body("Could not find user")
     } else {
-        HttpResponse::NotAcceptable()
+        info!(user_id = id, "deleted user");
+        HttpResponse::Ok().finish()
     })
 }
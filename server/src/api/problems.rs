@@ -1,8 +1,17 @@
 use actix_session::Session;
-use actix_web::{http, web, Error, HttpResponse, Responder};
-use common::problems;
+use actix_web::{error::BlockingError, http, web, Error, HttpResponse, Responder};
+use akshar_owo::metrics;
+use common::{
+    grading::Submission,
+    problems, random,
+    reports::NewReport,
+    user::Permission,
+    vetting::{self, VettedAnswer},
+};
+use diesel::prelude::*;
+use tracing::{debug, error, info};
 
-use crate::{database::models, validate, DbPool};
+use crate::{authorize, database::models, session_role, validate, DbPool};
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -12,7 +21,12 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .service(
                 web::scope("{id}")
                     .route("/", web::get().to(get))
-                    .route("/recommend/{undo}", web::get().to(recommend)),
+                    .route("/submit", web::post().to(submit))
+                    .route("/recommend/{undo}", web::get().to(recommend))
+                    .route("/report", web::post().to(report))
+                    .route("/vetting", web::get().to(vetted_answers))
+                    .route("/hide/{hidden}", web::post().to(hide))
+                    .route("/tags", web::put().to(set_tags)),
             ),
     );
 }
@@ -22,14 +36,18 @@ async fn index(
     pool: web::Data<DbPool>,
     web::Query(req): web::Query<models::ProblemQuery>,
 ) -> Result<impl Responder, Error> {
-    if !validate(&session, pool.clone()).await? {
-        return Ok(HttpResponse::NotAcceptable().finish());
-    }
+    let role = match session_role(&session, pool.clone()).await? {
+        Some(role) => role,
+        None => return Ok(HttpResponse::NotAcceptable().finish()),
+    };
+    let include_hidden = role.can(Permission::ModerateProblems);
     let conn = pool.get().expect("couldn't get db connection from pool");
-    let problems = web::block(move || req.query(&conn)).await.map_err(|e| {
-        eprintln!("{}", e);
-        HttpResponse::InternalServerError().finish()
-    })?;
+    let problems = metrics::block(move || req.query(include_hidden, &conn))
+        .await
+        .map_err(|e| {
+            error!("{}", e);
+            HttpResponse::InternalServerError().finish()
+        })?;
     Ok(HttpResponse::Ok()
         .header(http::header::CONTENT_TYPE, "application/json")
         .body(serde_json::to_string(&problems)?))
@@ -40,26 +58,30 @@ async fn create(
     pool: web::Data<DbPool>,
     req: web::Json<problems::NewProblem>,
 ) -> Result<impl Responder, Error> {
-    println!("{:?}", req);
+    debug!(owner_id = req.owner_id, topic_id = req.topic_id, "creating problem");
     if !validate(&session, pool.clone()).await?
         || req.owner_id != session.get::<models::SessionUser>("user")?.unwrap().id
     {
         return Ok(HttpResponse::NotAcceptable().finish());
     }
+    if let Err(error) = req.content.validate() {
+        return Ok(HttpResponse::BadRequest().body(error));
+    }
     let conn = pool.get().expect("couldn't get db connection from pool");
     let new_db_problem = models::NewDbProblem::from_new_problem(req.into_inner())
         .map_err(|_| HttpResponse::BadRequest().finish())?;
-    let new_problem = web::block(
+    let new_problem = metrics::block(
         move || -> Result<models::DbProblem, diesel::result::Error> {
             new_db_problem.insert(&conn)
         },
     )
     .await
     .map_err(|e| {
-        eprintln!("{}", e);
+        error!("{}", e);
         HttpResponse::InternalServerError().finish()
     })?;
-    Ok(HttpResponse::Ok().body(new_problem.into_problem()?.id.to_string()))
+    metrics::PROBLEMS_CREATED.inc();
+    Ok(HttpResponse::Ok().body(new_problem.id.to_string()))
 }
 
 async fn get(
@@ -67,18 +89,20 @@ async fn get(
     pool: web::Data<DbPool>,
     id: web::Path<i32>,
 ) -> Result<impl Responder, Error> {
-    if !validate(&session, pool.clone()).await? {
-        return Ok(HttpResponse::NotAcceptable().finish());
-    }
+    let role = match session_role(&session, pool.clone()).await? {
+        Some(role) => role,
+        None => return Ok(HttpResponse::NotAcceptable().finish()),
+    };
     let conn = pool.get().expect("couldn't get db connection from pool");
-    let db_problem = web::block(move || models::DbProblem::get_by_id(id.into_inner(), &conn))
+    let db_problem = metrics::block(move || models::DbProblem::get_by_id(id.into_inner(), &conn))
         .await
         .map_err(|e| {
-            eprintln!("{}", e);
+            error!("{}", e);
             HttpResponse::InternalServerError().finish()
-        })?;
-    Ok(if let Some(db_problem) = db_problem {
-        let problem: problems::Problem = db_problem.into_problem()?;
+        })?
+        .filter(|(db_problem, _)| !db_problem.hidden || role.can(Permission::ModerateProblems));
+    Ok(if let Some((db_problem, db_topic)) = db_problem {
+        let problem: problems::Problem = db_problem.into_problem(db_topic)?;
         HttpResponse::Ok()
             .header(http::header::CONTENT_TYPE, "application/json")
             .body(serde_json::to_string(&problem).unwrap())
@@ -87,6 +111,122 @@ async fn get(
     })
 }
 
+async fn submit(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    req: web::Json<Submission>,
+) -> Result<impl Responder, Error> {
+    let role = match session_role(&session, pool.clone()).await? {
+        Some(role) => role,
+        None => return Ok(HttpResponse::NotAcceptable().finish()),
+    };
+    let user = session.get::<models::SessionUser>("user")?;
+    if user.is_none() {
+        return Ok(HttpResponse::BadRequest().body("Invalid session"));
+    }
+    let user_id = user.unwrap().id;
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let db_problem = metrics::block(move || models::DbProblem::get_by_id(id.into_inner(), &conn))
+        .await
+        .map_err(|e| {
+            error!("{}", e);
+            HttpResponse::InternalServerError().finish()
+        })?
+        .filter(|(db_problem, _)| !db_problem.hidden || role.can(Permission::ModerateProblems));
+    let (db_problem, db_topic) = match db_problem {
+        Some(found) => found,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+    let problem: problems::Problem = db_problem.into_problem(db_topic)?;
+    let seed = random::user_problem_seed(user_id, problem.id);
+    let submission = req.into_inner();
+    // Grading can be CPU heavy (expressions, templates, grids), so it stays off the workers
+    let graded = metrics::block(move || {
+        let grade = problem.content.grade(&submission, seed)?;
+        Ok((problem, submission, grade))
+    })
+    .await;
+    let (problem, submission, grade) = match graded {
+        Ok(graded) => graded,
+        Err(BlockingError::Error(error)) => {
+            metrics::SUBMISSIONS.with_label_values(&["invalid"]).inc();
+            return Ok(HttpResponse::BadRequest().body(error));
+        }
+        Err(BlockingError::Canceled) => {
+            error!("grading was canceled");
+            return Ok(HttpResponse::InternalServerError().finish());
+        }
+    };
+    let result = if grade.is_correct() {
+        "correct"
+    } else {
+        "incorrect"
+    };
+    metrics::SUBMISSIONS.with_label_values(&[result]).inc();
+    if !grade.is_correct() {
+        queue_for_vetting(&pool, &problem, user_id, submission).await;
+    }
+    Ok(HttpResponse::Ok()
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&grade)?))
+}
+
+/// Queues a wrong free response for the problem's owner to vet. Failing to
+/// queue it is only logged, since the submission has already been graded.
+async fn queue_for_vetting(
+    pool: &web::Data<DbPool>,
+    problem: &problems::Problem,
+    user_id: i32,
+    submission: Submission,
+) {
+    let response = match (&problem.content, submission) {
+        (problems::ProblemContent::FreeResponse { .. }, Submission::FreeResponse(response)) => {
+            response
+        }
+        _ => return,
+    };
+    let answer = vetting::normalize_answer(&response);
+    if answer.is_empty() || response.chars().count() > vetting::MAX_ANSWER_CHARS {
+        return;
+    }
+    let problem_id = problem.id;
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let recorded = metrics::block(move || {
+        models::DbAnswerCandidate::record(problem_id, &answer, user_id, &response, &conn)
+    })
+    .await;
+    if let Err(e) = recorded {
+        error!("{}", e);
+    }
+}
+
+/// How the owner vetted the signed in user's wrong answers to a problem.
+async fn vetted_answers(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    if !validate(&session, pool.clone()).await? {
+        return Ok(HttpResponse::NotAcceptable().finish());
+    }
+    let user_id = session.get::<models::SessionUser>("user")?.unwrap().id;
+    let id = id.into_inner();
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let answers =
+        metrics::block(move || models::VettedAnswerRow::for_submitter(user_id, id, &conn))
+            .await
+            .map_err(|e| {
+                error!("{}", e);
+                HttpResponse::InternalServerError().finish()
+            })?;
+    let answers: Vec<VettedAnswer> = answers
+        .into_iter()
+        .map(models::VettedAnswerRow::into_vetted_answer)
+        .collect();
+    Ok(HttpResponse::Ok().json(answers))
+}
+
 // TODO: Refactor to handle new routing
 async fn recommend(
     session: Session,
@@ -104,7 +244,7 @@ async fn recommend(
         return Ok(HttpResponse::BadRequest().body("Invalid session"));
     }
     let user: models::NewUser = user.unwrap().into();
-    let resp = web::block(
+    let resp = metrics::block(
         move || -> Result<Result<i32, &str>, diesel::result::Error> {
             let user = user.get(&conn)?;
             if let Some(mut user) = user {
@@ -112,7 +252,7 @@ async fn recommend(
                     return Ok(Err("Attempt to recommend already recommended problem"));
                 }
                 let problem = models::DbProblem::get_by_id(id, &conn)?;
-                if let Some(mut problem) = problem {
+                if let Some((mut problem, _)) = problem {
                     if undo {
                         match user
                             .recommended_ids
@@ -145,13 +285,153 @@ async fn recommend(
     )
     .await
     .map_err(|e| {
-        eprintln!("{}", e);
+        error!("{}", e);
         HttpResponse::InternalServerError().finish()
     })?;
     Ok(match resp {
-        Ok(rec_count) => HttpResponse::Ok()
-            .header(http::header::CONTENT_TYPE, "application/json")
-            .body(rec_count.to_string()),
+        Ok(rec_count) => {
+            let action = if undo { "undo" } else { "recommend" };
+            metrics::RECOMMENDATIONS.with_label_values(&[action]).inc();
+            HttpResponse::Ok()
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(rec_count.to_string())
+        }
         Err(error) => HttpResponse::NotFound().body(error),
     })
 }
+
+async fn report(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    req: web::Json<NewReport>,
+) -> Result<impl Responder, Error> {
+    let role = match session_role(&session, pool.clone()).await? {
+        Some(role) => role,
+        None => return Ok(HttpResponse::NotAcceptable().finish()),
+    };
+    if let Err(error) = req.validate() {
+        return Ok(HttpResponse::BadRequest().body(error));
+    }
+    let reporter_id = session.get::<models::SessionUser>("user")?.unwrap().id;
+    let id = id.into_inner();
+    let req = req.into_inner();
+    let reason = req.reason;
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let report = metrics::block(
+        move || -> Result<Option<models::DbReport>, diesel::result::Error> {
+            let visible = models::DbProblem::get_by_id(id, &conn)?.map_or(false, |(problem, _)| {
+                !problem.hidden || role.can(Permission::ModerateProblems)
+            });
+            if !visible {
+                return Ok(None);
+            }
+            let report = models::NewDbReport {
+                problem_id: id,
+                reporter_id,
+                reason: req.reason,
+                comment: req.comment.trim().to_string(),
+            };
+            report.insert(&conn).map(Some)
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(if let Some(report) = report {
+        info!(problem_id = id, report_id = report.id, %reason, "problem reported");
+        metrics::REPORTS
+            .with_label_values(&[&reason.to_string()])
+            .inc();
+        HttpResponse::Ok().body(report.id.to_string())
+    } else {
+        HttpResponse::NotFound().body("Could not find requested problem")
+    })
+}
+
+async fn hide(
+    session: Session,
+    pool: web::Data<DbPool>,
+    req: web::Path<(i32, bool)>,
+) -> Result<impl Responder, Error> {
+    if !authorize(&session, pool.clone(), Permission::ModerateProblems).await? {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    let actor_id = session.get::<models::SessionUser>("user")?.unwrap().id;
+    let (id, hidden) = (req.0, req.1);
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let changed = metrics::block(move || {
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let changed = models::DbProblem::set_hidden(id, hidden, &conn)?;
+            if changed > 0 {
+                let action = if hidden {
+                    "hide_problem"
+                } else {
+                    "unhide_problem"
+                };
+                models::NewAuditEntry {
+                    problem_id: Some(id),
+                    ..models::NewAuditEntry::new(actor_id, action)
+                }
+                .insert(&conn)?;
+            }
+            Ok(changed)
+        })
+    })
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(if changed == 0 {
+        HttpResponse::NotFound().body("Could not find requested problem")
+    } else {
+        info!(problem_id = id, hidden, "moderated problem visibility");
+        HttpResponse::Ok().finish()
+    })
+}
+
+async fn set_tags(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    req: web::Json<Vec<String>>,
+) -> Result<impl Responder, Error> {
+    if !authorize(&session, pool.clone(), Permission::ModerateProblems).await? {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    let tags: Vec<String> = req.iter().map(|tag| tag.trim().to_string()).collect();
+    if tags.iter().any(String::is_empty) {
+        return Ok(HttpResponse::BadRequest().body("Tags must not be blank"));
+    }
+    let actor_id = session.get::<models::SessionUser>("user")?.unwrap().id;
+    let id = id.into_inner();
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let changed = metrics::block(move || {
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let changed = models::DbProblem::set_tags(id, &tags, &conn)?;
+            if changed > 0 {
+                models::NewAuditEntry {
+                    problem_id: Some(id),
+                    detail: tags.join(", "),
+                    ..models::NewAuditEntry::new(actor_id, "set_tags")
+                }
+                .insert(&conn)?;
+            }
+            Ok(changed)
+        })
+    })
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(if changed == 0 {
+        HttpResponse::NotFound().body("Could not find requested problem")
+    } else {
+        info!(problem_id = id, "moderated problem tags");
+        HttpResponse::Ok().finish()
+    })
+}
@@ -0,0 +1,223 @@
+use actix_session::Session;
+use actix_web::{http::StatusCode, web, Error, HttpResponse, Responder};
+use akshar_owo::metrics;
+use common::{
+    reports::{AuditEntry, Report, ReportOutcome, Resolution},
+    user::{Permission, Role},
+};
+use diesel::prelude::*;
+use tracing::{error, info};
+
+use crate::{database::models, session_role, DbPool};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/")
+            .route("", web::get().to(queue))
+            .route("/audit", web::get().to(audit))
+            .route("/{id}/claim", web::post().to(claim))
+            .route("/{id}/resolve", web::post().to(resolve)),
+    );
+}
+
+/// The signed in moderator's id and role, if they may handle reports.
+async fn moderator(
+    session: &Session,
+    pool: web::Data<DbPool>,
+) -> Result<Option<(i32, Role)>, Error> {
+    match session_role(session, pool).await? {
+        Some(role) if role.can(Permission::HandleReports) => {
+            let user = session.get::<models::SessionUser>("user")?;
+            Ok(user.map(|user| (user.id, role)))
+        }
+        _ => Ok(None),
+    }
+}
+
+async fn queue(
+    session: Session,
+    pool: web::Data<DbPool>,
+    web::Query(req): web::Query<models::ReportQuery>,
+) -> Result<impl Responder, Error> {
+    if moderator(&session, pool.clone()).await?.is_none() {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let reports = metrics::block(move || req.query(&conn))
+        .await
+        .map_err(|e| {
+            error!("{}", e);
+            HttpResponse::InternalServerError().finish()
+        })?;
+    let reports: Vec<Report> = reports
+        .into_iter()
+        .map(models::DbReport::into_report)
+        .collect();
+    Ok(HttpResponse::Ok().json(reports))
+}
+
+async fn claim(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    let actor_id = match moderator(&session, pool.clone()).await? {
+        Some((actor_id, _)) => actor_id,
+        None => return Ok(HttpResponse::Forbidden().finish()),
+    };
+    let id = id.into_inner();
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let resp = metrics::block(move || {
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let report = match models::DbReport::get_for_update(id, &conn)? {
+                Some(report) => report,
+                None => return Ok(Err((StatusCode::NOT_FOUND, "Could not find report"))),
+            };
+            if !report.is_open_to(actor_id) {
+                return Ok(Err((
+                    StatusCode::CONFLICT,
+                    "Report is already resolved or claimed by someone else",
+                )));
+            }
+            let report = models::DbReport::claim(id, actor_id, &conn)?;
+            models::NewAuditEntry {
+                report_id: Some(id),
+                problem_id: Some(report.problem_id),
+                ..models::NewAuditEntry::new(actor_id, "claim_report")
+            }
+            .insert(&conn)?;
+            Ok(Ok(report))
+        })
+    })
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(match resp {
+        Ok(report) => {
+            info!(report_id = id, "claimed report");
+            HttpResponse::Ok().json(report.into_report())
+        }
+        Err((status, message)) => HttpResponse::build(status).body(message),
+    })
+}
+
+async fn resolve(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    req: web::Json<Resolution>,
+) -> Result<impl Responder, Error> {
+    let (actor_id, actor_role) = match moderator(&session, pool.clone()).await? {
+        Some(moderator) => moderator,
+        None => return Ok(HttpResponse::Forbidden().finish()),
+    };
+    if let Err(error) = req.validate() {
+        return Ok(HttpResponse::BadRequest().body(error));
+    }
+    let (id, resolution) = (id.into_inner(), req.into_inner());
+    let outcome = resolution.outcome;
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let resp = metrics::block(move || {
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let report = match models::DbReport::get_for_update(id, &conn)? {
+                Some(report) => report,
+                None => return Ok(Err((StatusCode::NOT_FOUND, "Could not find report"))),
+            };
+            if !report.is_open_to(actor_id) {
+                return Ok(Err((
+                    StatusCode::CONFLICT,
+                    "Report is already resolved or claimed by someone else",
+                )));
+            }
+            // Reports are deleted along with their problem, so it's still there
+            let owner_id = match models::DbProblem::get_by_id(report.problem_id, &conn)? {
+                Some((problem, _)) => problem.owner_id,
+                None => return Ok(Err((StatusCode::NOT_FOUND, "Could not find problem"))),
+            };
+            // Everything is checked before anything is changed, so returning
+            // early never leaves half an outcome applied
+            if let ReportOutcome::WarnOwner | ReportOutcome::SuspendOwner = outcome {
+                let owner = match models::User::get_by_id(owner_id, &conn)? {
+                    Some(owner) => owner,
+                    None => {
+                        return Ok(Err((
+                            StatusCode::NOT_FOUND,
+                            "Could not find problem's owner",
+                        )))
+                    }
+                };
+                if owner.role > Role::User && actor_role < Role::Admin {
+                    return Ok(Err((
+                        StatusCode::FORBIDDEN,
+                        "Only admins can warn or suspend moderators",
+                    )));
+                }
+            }
+            match outcome {
+                ReportOutcome::Dismiss => {}
+                ReportOutcome::HideProblem => {
+                    models::DbProblem::set_hidden(report.problem_id, true, &conn)?;
+                }
+                ReportOutcome::WarnOwner => {
+                    models::User::warn(owner_id, &conn)?;
+                }
+                ReportOutcome::SuspendOwner => {
+                    models::User::set_suspended(owner_id, true, &conn)?;
+                }
+            }
+            let report = models::DbReport::resolve(id, actor_id, &resolution, &conn)?;
+            let affects_owner = matches!(
+                outcome,
+                ReportOutcome::WarnOwner | ReportOutcome::SuspendOwner
+            );
+            models::NewAuditEntry {
+                report_id: Some(id),
+                problem_id: Some(report.problem_id),
+                target_user_id: if affects_owner { Some(owner_id) } else { None },
+                detail: format!("{}: {}", outcome, resolution.note),
+                ..models::NewAuditEntry::new(actor_id, "resolve_report")
+            }
+            .insert(&conn)?;
+            Ok(Ok(report))
+        })
+    })
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(match resp {
+        Ok(report) => {
+            info!(report_id = id, %outcome, "resolved report");
+            metrics::REPORTS_RESOLVED
+                .with_label_values(&[&outcome.to_string()])
+                .inc();
+            HttpResponse::Ok().json(report.into_report())
+        }
+        Err((status, message)) => HttpResponse::build(status).body(message),
+    })
+}
+
+async fn audit(
+    session: Session,
+    pool: web::Data<DbPool>,
+    web::Query(req): web::Query<models::AuditQuery>,
+) -> Result<impl Responder, Error> {
+    if moderator(&session, pool.clone()).await?.is_none() {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let entries = metrics::block(move || req.query(&conn))
+        .await
+        .map_err(|e| {
+            error!("{}", e);
+            HttpResponse::InternalServerError().finish()
+        })?;
+    let entries: Vec<AuditEntry> = entries
+        .into_iter()
+        .map(models::DbAuditEntry::into_entry)
+        .collect();
+    Ok(HttpResponse::Ok().json(entries))
+}
@@ -0,0 +1,158 @@
+use actix_session::Session;
+use actix_web::{http, web, Error, HttpResponse, Responder};
+use akshar_owo::metrics;
+use common::{
+    problems::{NewTopic, Topic},
+    user::Permission,
+};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use tracing::error;
+
+use crate::{authorize, database::models, validate, DbPool};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/")
+            .route("", web::get().to(index))
+            .route("", web::post().to(create))
+            .service(
+                web::scope("{id}")
+                    .route("/", web::put().to(update))
+                    .route("/", web::delete().to(delete)),
+            ),
+    );
+}
+
+/// Turns constraint violations into messages for the admin, passing other errors on.
+fn constraint_error(error: DieselError) -> Result<&'static str, DieselError> {
+    match error {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            Ok("A topic with that slug already exists")
+        }
+        DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+            Ok("Topic still has problems or subtopics, or its parent does not exist")
+        }
+        error => Err(error),
+    }
+}
+
+async fn index(session: Session, pool: web::Data<DbPool>) -> Result<impl Responder, Error> {
+    if !validate(&session, pool.clone()).await? {
+        return Ok(HttpResponse::NotAcceptable().finish());
+    }
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let topics = metrics::block(move || models::DbTopic::all(&conn))
+        .await
+        .map_err(|e| {
+            error!("{}", e);
+            HttpResponse::InternalServerError().finish()
+        })?;
+    let topics: Vec<Topic> = topics.into_iter().map(models::DbTopic::into_topic).collect();
+    Ok(HttpResponse::Ok()
+        .header(http::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string(&topics)?))
+}
+
+async fn create(
+    session: Session,
+    pool: web::Data<DbPool>,
+    req: web::Json<NewTopic>,
+) -> Result<impl Responder, Error> {
+    if !authorize(&session, pool.clone(), Permission::ManageTopics).await? {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    if let Err(error) = req.validate() {
+        return Ok(HttpResponse::BadRequest().body(error));
+    }
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let new_topic = models::NewDbTopic::from_new_topic(req.into_inner());
+    let resp = metrics::block(
+        move || -> Result<Result<models::DbTopic, &str>, diesel::result::Error> {
+            match new_topic.insert(&conn) {
+                Ok(topic) => Ok(Ok(topic)),
+                Err(error) => constraint_error(error).map(Err),
+            }
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(match resp {
+        Ok(topic) => HttpResponse::Ok()
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_string(&topic.into_topic())?),
+        Err(error) => HttpResponse::BadRequest().body(error),
+    })
+}
+
+async fn update(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    req: web::Json<NewTopic>,
+) -> Result<impl Responder, Error> {
+    if !authorize(&session, pool.clone(), Permission::ManageTopics).await? {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    if let Err(error) = req.validate() {
+        return Ok(HttpResponse::BadRequest().body(error));
+    }
+    let id = id.into_inner();
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let topic = models::NewDbTopic::from_new_topic(req.into_inner());
+    let resp = metrics::block(
+        move || -> Result<Result<Option<models::DbTopic>, &str>, diesel::result::Error> {
+            if models::DbTopic::would_cycle(id, topic.parent_id, &conn)? {
+                return Ok(Err("A topic cannot be placed under itself or its subtopics"));
+            }
+            match topic.update(id, &conn) {
+                Ok(topic) => Ok(Ok(topic)),
+                Err(error) => constraint_error(error).map(Err),
+            }
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(match resp {
+        Ok(Some(topic)) => HttpResponse::Ok()
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_string(&topic.into_topic())?),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(error) => HttpResponse::BadRequest().body(error),
+    })
+}
+
+async fn delete(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    if !authorize(&session, pool.clone(), Permission::ManageTopics).await? {
+        return Ok(HttpResponse::Forbidden().finish());
+    }
+    let id = id.into_inner();
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let resp = metrics::block(
+        move || -> Result<Result<usize, &str>, diesel::result::Error> {
+            match models::DbTopic::delete(id, &conn) {
+                Ok(deleted) => Ok(Ok(deleted)),
+                Err(error) => constraint_error(error).map(Err),
+            }
+        },
+    )
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(match resp {
+        Ok(0) => HttpResponse::NotFound().finish(),
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(error) => HttpResponse::BadRequest().body(error),
+    })
+}
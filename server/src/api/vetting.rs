@@ -0,0 +1,209 @@
+use actix_session::Session;
+use actix_web::{http::StatusCode, web, Error, HttpResponse, Responder};
+use akshar_owo::metrics;
+use common::{
+    grading::Submission,
+    problems::ProblemContent,
+    random,
+    vetting::{self, AnswerCandidate, Rejection, VettingStatus},
+};
+use diesel::prelude::*;
+use tracing::{error, info};
+
+use crate::{database::models, validate, DbPool};
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/")
+            .route("", web::get().to(queue))
+            .route("/{id}/accept", web::post().to(accept))
+            .route("/{id}/reject", web::post().to(reject)),
+    );
+}
+
+/// The signed in user's id, if the session is valid.
+async fn session_user_id(session: &Session, pool: web::Data<DbPool>) -> Result<Option<i32>, Error> {
+    if !validate(session, pool).await? {
+        return Ok(None);
+    }
+    Ok(session
+        .get::<models::SessionUser>("user")?
+        .map(|user| user.id))
+}
+
+/// Wrong answers to the signed in user's problems.
+async fn queue(
+    session: Session,
+    pool: web::Data<DbPool>,
+    web::Query(req): web::Query<models::VettingQuery>,
+) -> Result<impl Responder, Error> {
+    let owner_id = match session_user_id(&session, pool.clone()).await? {
+        Some(owner_id) => owner_id,
+        None => return Ok(HttpResponse::NotAcceptable().finish()),
+    };
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let candidates = metrics::block(move || req.query(owner_id, &conn))
+        .await
+        .map_err(|e| {
+            error!("{}", e);
+            HttpResponse::InternalServerError().finish()
+        })?;
+    let candidates: Vec<AnswerCandidate> = candidates
+        .into_iter()
+        .map(models::CandidateSummary::into_candidate)
+        .collect();
+    Ok(HttpResponse::Ok().json(candidates))
+}
+
+/// Locks a pending candidate and its problem's stored content, if the
+/// signed in user owns the problem.
+fn pending_candidate(
+    candidate_id: i32,
+    owner_id: i32,
+    conn: &PgConnection,
+) -> Result<
+    Result<(models::DbAnswerCandidate, models::DbProblem), (StatusCode, &'static str)>,
+    diesel::result::Error,
+> {
+    // The problem is locked before the candidate, in the same order as
+    // recording a submission locks them, so the two can't deadlock
+    let problem_id = match models::DbAnswerCandidate::get_by_id(candidate_id, conn)? {
+        Some(candidate) => candidate.problem_id,
+        None => return Ok(Err((StatusCode::NOT_FOUND, "Could not find answer"))),
+    };
+    let problem = match models::DbProblem::get_for_update(problem_id, conn)? {
+        Some(problem) => problem,
+        None => return Ok(Err((StatusCode::NOT_FOUND, "Could not find problem"))),
+    };
+    let candidate = match models::DbAnswerCandidate::get_for_update(candidate_id, conn)? {
+        Some(candidate) => candidate,
+        None => return Ok(Err((StatusCode::NOT_FOUND, "Could not find answer"))),
+    };
+    if problem.owner_id != owner_id {
+        return Ok(Err((
+            StatusCode::FORBIDDEN,
+            "Only the problem's owner can vet its answers",
+        )));
+    }
+    if candidate.status != VettingStatus::Pending {
+        return Ok(Err((
+            StatusCode::CONFLICT,
+            "Answer has already been vetted",
+        )));
+    }
+    Ok(Ok((candidate, problem)))
+}
+
+/// Adds a candidate to its problem's solutions and regrades everyone who
+/// gave it, returning how many of them are now correct.
+async fn accept(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+) -> Result<impl Responder, Error> {
+    let owner_id = match session_user_id(&session, pool.clone()).await? {
+        Some(owner_id) => owner_id,
+        None => return Ok(HttpResponse::NotAcceptable().finish()),
+    };
+    let id = id.into_inner();
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let resp = metrics::block(move || {
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let (candidate, problem) = match pending_candidate(id, owner_id, &conn)? {
+                Ok(pending) => pending,
+                Err(error) => return Ok(Err(error)),
+            };
+            let mut content = ProblemContent::from_stored(problem.content_version, problem.data)
+                .map_err(|e| diesel::result::Error::DeserializationError(Box::new(e)))?;
+            match &mut content {
+                ProblemContent::FreeResponse { solution, .. } => {
+                    solution.push(vetting::solution_for(&candidate.example))
+                }
+                _ => {
+                    return Ok(Err((
+                        StatusCode::CONFLICT,
+                        "Problem is no longer free response",
+                    )))
+                }
+            }
+            models::DbProblem::set_content(problem.id, &content, &conn)?;
+            let mut now_correct = 0;
+            for submission in models::CandidateSubmission::for_candidate(candidate.id, &conn)? {
+                let seed = random::user_problem_seed(submission.user_id, problem.id);
+                let correct = content
+                    .grade(&Submission::FreeResponse(submission.response), seed)
+                    .map_or(false, |grade| grade.is_correct());
+                models::CandidateSubmission::set_correct(
+                    candidate.id,
+                    submission.user_id,
+                    correct,
+                    &conn,
+                )?;
+                now_correct += correct as usize;
+            }
+            models::DbAnswerCandidate::set_status(
+                candidate.id,
+                VettingStatus::Accepted,
+                None,
+                &conn,
+            )?;
+            Ok(Ok(now_correct))
+        })
+    })
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(match resp {
+        Ok(now_correct) => {
+            info!(candidate_id = id, now_correct, "accepted answer");
+            HttpResponse::Ok().json(now_correct)
+        }
+        Err((status, message)) => HttpResponse::build(status).body(message),
+    })
+}
+
+async fn reject(
+    session: Session,
+    pool: web::Data<DbPool>,
+    id: web::Path<i32>,
+    req: web::Json<Rejection>,
+) -> Result<impl Responder, Error> {
+    let owner_id = match session_user_id(&session, pool.clone()).await? {
+        Some(owner_id) => owner_id,
+        None => return Ok(HttpResponse::NotAcceptable().finish()),
+    };
+    if let Err(error) = req.validate() {
+        return Ok(HttpResponse::BadRequest().body(error));
+    }
+    let (id, rejection) = (id.into_inner(), req.into_inner());
+    let conn = pool.get().expect("couldn't get db connection from pool");
+    let resp = metrics::block(move || {
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let (candidate, _) = match pending_candidate(id, owner_id, &conn)? {
+                Ok(pending) => pending,
+                Err(error) => return Ok(Err(error)),
+            };
+            models::DbAnswerCandidate::set_status(
+                candidate.id,
+                VettingStatus::Rejected,
+                Some(rejection.note.trim()),
+                &conn,
+            )?;
+            Ok(Ok(()))
+        })
+    })
+    .await
+    .map_err(|e| {
+        error!("{}", e);
+        HttpResponse::InternalServerError().finish()
+    })?;
+    Ok(match resp {
+        Ok(()) => {
+            info!(candidate_id = id, "rejected answer");
+            HttpResponse::Ok().finish()
+        }
+        Err((status, message)) => HttpResponse::build(status).body(message),
+    })
+}
@@ -0,0 +1,195 @@
+//! Operator tooling that works directly against the database.
+
+use akshar_owo::{
+    config::Config,
+    database::{migrations, models},
+};
+use common::{
+    problems::{NewProblem, Problem},
+    user::Role,
+};
+use diesel::{pg::PgConnection, prelude::*};
+use std::{env, fs, io, process};
+
+const USAGE: &str = "Usage: akshar_admin [--config <file>] [--database-url <url>] <command>
+
+Commands:
+    create-user <name> <password>
+    delete-user <name>
+    reset-password <name> <password>
+    promote <name> [moderator|admin]    (defaults to moderator)
+    demote <name>
+    suspend <name>
+    unsuspend <name>
+    migrate
+    recount-recommendations
+    reindex
+    export <file>
+    import <file>";
+
+fn create_user(conn: &PgConnection, name: &str, pass: &str) -> Result<String, String> {
+    if models::User::get_by_name(name.to_string(), conn)
+        .map_err(|e| e.to_string())?
+        .is_some()
+    {
+        return Err(format!("User {} already exists", name));
+    }
+    let user = models::NewUser {
+        name: name.to_string(),
+        pass: pass.to_string(),
+    }
+    .insert(conn)
+    .map_err(|e| e.to_string())?;
+    Ok(format!("Created user {} with id {}", user.name, user.id))
+}
+
+fn delete_user(conn: &PgConnection, name: &str) -> Result<String, String> {
+    match models::User::delete_by_name(name, conn).map_err(|e| e.to_string())? {
+        0 => Err(format!("No user named {}", name)),
+        _ => Ok(format!("Deleted user {} (their problems were kept)", name)),
+    }
+}
+
+fn reset_password(conn: &PgConnection, name: &str, pass: &str) -> Result<String, String> {
+    match models::User::set_password(name, pass, conn).map_err(|e| e.to_string())? {
+        0 => Err(format!("No user named {}", name)),
+        _ => Ok(format!("Reset the password of {}", name)),
+    }
+}
+
+fn set_role(conn: &PgConnection, name: &str, role: &str) -> Result<String, String> {
+    let role: Role = role.parse()?;
+    match models::User::set_role(name, role, conn).map_err(|e| e.to_string())? {
+        0 => Err(format!("No user named {}", name)),
+        _ => Ok(format!("{} is now a {}", name, role)),
+    }
+}
+
+fn set_suspended(conn: &PgConnection, name: &str, suspended: bool) -> Result<String, String> {
+    match models::User::set_suspended_by_name(name, suspended, conn).map_err(|e| e.to_string())? {
+        0 => Err(format!("No user named {}", name)),
+        _ if suspended => Ok(format!("Suspended {}", name)),
+        _ => Ok(format!("Lifted the suspension of {}", name)),
+    }
+}
+
+fn migrate(conn: &PgConnection) -> Result<String, String> {
+    migrations::run(conn, &mut io::stdout()).map_err(|e| e.to_string())?;
+    Ok(format!("Schema is at version {}", migrations::SCHEMA_VERSION))
+}
+
+fn recount_recommendations(conn: &PgConnection) -> Result<String, String> {
+    let changed = models::DbProblem::recount_recommendations(conn).map_err(|e| e.to_string())?;
+    Ok(format!("Corrected the recommendation count of {} problems", changed))
+}
+
+fn reindex(conn: &PgConnection) -> Result<String, String> {
+    models::DbProblem::reindex_search(conn).map_err(|e| e.to_string())?;
+    Ok("Rebuilt the problem and topic search indexes".to_string())
+}
+
+fn export(conn: &PgConnection, path: &str) -> Result<String, String> {
+    let problems = models::DbProblem::all(conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(problem, topic)| problem.into_problem(topic))
+        .collect::<Result<Vec<Problem>, _>>()
+        .map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&problems).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("Could not write {}: {}", path, e))?;
+    Ok(format!("Exported {} problems to {}", problems.len(), path))
+}
+
+/// Imports problems written by `export` as new problems, matching topics by slug.
+fn import(conn: &PgConnection, path: &str) -> Result<String, String> {
+    let json = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+    let problems: Vec<Problem> = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    let topics = models::DbTopic::all(conn).map_err(|e| e.to_string())?;
+    let mut new_problems = Vec::with_capacity(problems.len());
+    for problem in problems {
+        let topic = topics
+            .iter()
+            .find(|topic| topic.slug == problem.topic.slug)
+            .ok_or_else(|| {
+                format!(
+                    "Problem {} has topic {}, which doesn't exist here",
+                    problem.id, problem.topic.slug
+                )
+            })?;
+        problem
+            .content
+            .validate()
+            .map_err(|error| format!("Problem {} is invalid: {}", problem.id, error))?;
+        let hidden = problem.hidden;
+        let new_problem = models::NewDbProblem::from_new_problem(NewProblem {
+            owner_id: problem.owner_id,
+            topic_id: topic.id,
+            tags: problem.tags,
+            prompt: problem.prompt,
+            content: problem.content,
+            explanation: problem.explanation,
+        })
+        .map_err(|e| e.to_string())?;
+        new_problems.push((new_problem, hidden));
+    }
+    conn.transaction(|| -> Result<(), diesel::result::Error> {
+        for (new_problem, hidden) in &new_problems {
+            let inserted = new_problem.insert(conn)?;
+            if *hidden {
+                models::DbProblem::set_hidden(inserted.id, true, conn)?;
+            }
+        }
+        Ok(())
+    })
+    .map_err(|e| e.to_string())?;
+    Ok(format!("Imported {} problems from {}", new_problems.len(), path))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (config, commands) = Config::load(&args).unwrap_or_else(|error| {
+        eprintln!("Invalid configuration: {}", error);
+        process::exit(1);
+    });
+    let args: Vec<&str> = commands.iter().map(String::as_str).collect();
+    if args.is_empty() {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+
+    let conn = PgConnection::establish(&config.database.url()).unwrap_or_else(|e| {
+        eprintln!("Could not connect to the database: {}", e);
+        process::exit(1);
+    });
+    if let Err(error) = migrations::check_schema_version(&conn) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+
+    let result = match args.as_slice() {
+        ["create-user", name, pass] => create_user(&conn, name, pass),
+        ["delete-user", name] => delete_user(&conn, name),
+        ["reset-password", name, pass] => reset_password(&conn, name, pass),
+        ["promote", name] => set_role(&conn, name, "moderator"),
+        ["promote", name, role] => set_role(&conn, name, role),
+        ["demote", name] => set_role(&conn, name, "user"),
+        ["suspend", name] => set_suspended(&conn, name, true),
+        ["unsuspend", name] => set_suspended(&conn, name, false),
+        ["migrate"] => migrate(&conn),
+        ["recount-recommendations"] => recount_recommendations(&conn),
+        ["reindex"] => reindex(&conn),
+        ["export", path] => export(&conn, path),
+        ["import", path] => import(&conn, path),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    match result {
+        Ok(message) => println!("{}", message),
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    }
+}
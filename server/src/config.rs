@@ -0,0 +1,613 @@
+//! Server settings, layered from defaults, a TOML file,
+//! environment variables and finally command line flags.
+
+use actix_web::http::{header::HeaderName, Method};
+use serde::Deserialize;
+use std::{collections::BTreeMap, env, fmt, fs, path::PathBuf, time::Duration};
+use tracing_subscriber::EnvFilter;
+
+/// Where the config file is looked for if `--config` and `AKSHAR_CONFIG` aren't given.
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub tls: Option<TlsConfig>,
+    pub session: SessionConfig,
+    pub cors: CorsConfig,
+    pub rate_limit: RateLimitConfig,
+    pub logging: LoggingConfig,
+    /// The built client, served at `/`.
+    pub frontend_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Defaults to the number of CPUs.
+    pub workers: Option<usize>,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    /// A full connection string, used instead of the fields below if set.
+    pub url: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+    pub name: String,
+    pub pool_size: u32,
+    pub min_idle: Option<u32>,
+    pub connection_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionConfig {
+    /// Signs the session cookie. Must be at least 32 bytes, and set in release builds.
+    pub key: Option<String>,
+    pub cookie_name: String,
+    /// Defaults to `server.host`.
+    pub domain: Option<String>,
+    pub secure: bool,
+    pub max_age_secs: Option<i64>,
+    /// `strict`, `lax` or `none`. Cookies sent to an API on another site need
+    /// `none`, which browsers only accept along with `secure`.
+    pub same_site: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CorsProfile {
+    /// Lets the webpack dev server on port 8000 call the API with cookies.
+    Development,
+    /// Allows no other origins unless they're listed.
+    Production,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Defaults to development in debug builds and production otherwise.
+    pub profile: CorsProfile,
+    /// Origins allowed to make cross-origin requests, overriding the profile's.
+    /// No origins disables CORS.
+    pub allowed_origins: Option<Vec<String>>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+    /// Overrides the profile's choice of whether cookies may be sent cross-origin.
+    pub supports_credentials: Option<bool>,
+    pub max_age_secs: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitStore {
+    /// Per process, so each server instance limits separately.
+    Memory,
+    /// Shared by every server instance using the database.
+    Postgres,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RouteLimit {
+    /// Zero exempts the route from rate limiting.
+    pub requests_per_minute: u32,
+    /// How many requests may be made at once before the limit applies.
+    pub burst: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Requests each client may make per minute to routes not listed in
+    /// `routes`. Zero leaves them unlimited.
+    pub requests_per_minute: u32,
+    pub burst: u32,
+    /// Limits for paths starting with the given prefix, where a `*` segment
+    /// matches any one segment. The longest matching prefix wins.
+    pub routes: BTreeMap<String, RouteLimit>,
+    pub store: RateLimitStore,
+    /// Use the `X-Forwarded-For` header for client addresses. Only turn this
+    /// on behind a proxy that sets it, or clients can pick their own address.
+    pub trust_forwarded_for: bool,
+    /// How long logins to an account from an address are refused after a failed attempt.
+    /// This doubles with each further failure, up to `login_backoff_max_secs`.
+    pub login_backoff_secs: u64,
+    pub login_backoff_max_secs: u64,
+    /// Leading zero bits the proof of work for signing up needs. Each bit
+    /// doubles the work; zero turns the challenge off.
+    pub signup_difficulty: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Json,
+    Logfmt,
+    /// Human-readable, for development.
+    Pretty,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// A filter such as `info` or `akshar_owo=debug,info`. `RUST_LOG` overrides it.
+    pub level: String,
+}
+
+// Written out so secrets never end up in logs
+
+impl fmt::Debug for DatabaseConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DatabaseConfig")
+            .field("url", &self.url.as_ref().map(|_| "[redacted]"))
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("user", &self.user)
+            .field("password", &"[redacted]")
+            .field("name", &self.name)
+            .field("pool_size", &self.pool_size)
+            .field("min_idle", &self.min_idle)
+            .field("connection_timeout_secs", &self.connection_timeout_secs)
+            .finish()
+    }
+}
+
+impl fmt::Debug for SessionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SessionConfig")
+            .field("key", &self.key.as_ref().map(|_| "[redacted]"))
+            .field("cookie_name", &self.cookie_name)
+            .field("domain", &self.domain)
+            .field("secure", &self.secure)
+            .field("max_age_secs", &self.max_age_secs)
+            .field("same_site", &self.same_site)
+            .finish()
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            server: ServerConfig::default(),
+            database: DatabaseConfig::default(),
+            tls: None,
+            session: SessionConfig::default(),
+            cors: CorsConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            logging: LoggingConfig::default(),
+            frontend_path: "../client/dist".into(),
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 8080,
+            workers: None,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            host: "localhost".to_string(),
+            port: 5432,
+            user: "postgres".to_string(),
+            password: "postgres".to_string(),
+            name: "akshardb".to_string(),
+            pool_size: 10,
+            min_idle: None,
+            connection_timeout_secs: 30,
+        }
+    }
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            key: None,
+            cookie_name: "actix-session".to_string(),
+            domain: None,
+            secure: false,
+            max_age_secs: None,
+            same_site: None,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            profile: if cfg!(debug_assertions) {
+                CorsProfile::Development
+            } else {
+                CorsProfile::Production
+            },
+            allowed_origins: None,
+            allowed_methods: ["GET", "POST", "PUT", "DELETE", "OPTIONS"]
+                .iter()
+                .map(|method| method.to_string())
+                .collect(),
+            allowed_headers: vec!["Content-Type".to_string()],
+            supports_credentials: None,
+            max_age_secs: Some(3600),
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let limit = |requests_per_minute, burst| RouteLimit {
+            requests_per_minute,
+            burst,
+        };
+        let mut routes = BTreeMap::new();
+        routes.insert("/api/account/create".to_string(), limit(5, 5));
+        routes.insert("/api/account/login".to_string(), limit(10, 10));
+        routes.insert("/api/problems/*/recommend".to_string(), limit(30, 10));
+        routes.insert("/api/problems/*/report".to_string(), limit(5, 5));
+        Self {
+            requests_per_minute: 0,
+            burst: 20,
+            routes,
+            store: RateLimitStore::Memory,
+            trust_forwarded_for: false,
+            login_backoff_secs: 1,
+            login_backoff_max_secs: 15 * 60,
+            signup_difficulty: 0,
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: if cfg!(debug_assertions) {
+                LogFormat::Pretty
+            } else {
+                LogFormat::Json
+            },
+            level: "info".to_string(),
+        }
+    }
+}
+
+/// Quotes a value for a libpq keyword/value connection string.
+fn quote_conn_value(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+impl DatabaseConfig {
+    pub fn url(&self) -> String {
+        match &self.url {
+            Some(url) => url.clone(),
+            None => format!(
+                "host={} port={} user={} password={} dbname={}",
+                quote_conn_value(&self.host),
+                self.port,
+                quote_conn_value(&self.user),
+                quote_conn_value(&self.password),
+                quote_conn_value(&self.name)
+            ),
+        }
+    }
+
+    pub fn connection_timeout(&self) -> Duration {
+        Duration::from_secs(self.connection_timeout_secs)
+    }
+}
+
+impl SessionConfig {
+    /// The signing key, falling back to an all-zero key in debug builds.
+    pub fn key_bytes(&self) -> Vec<u8> {
+        match &self.key {
+            Some(key) => key.as_bytes().to_vec(),
+            None => vec![0; 32],
+        }
+    }
+}
+
+impl CorsConfig {
+    pub fn origins(&self) -> Vec<String> {
+        match (&self.allowed_origins, self.profile) {
+            (Some(origins), _) => origins.clone(),
+            (None, CorsProfile::Development) => vec!["http://localhost:8000".to_string()],
+            (None, CorsProfile::Production) => vec![],
+        }
+    }
+
+    pub fn credentials(&self) -> bool {
+        self.supports_credentials
+            .unwrap_or(self.profile == CorsProfile::Development)
+    }
+}
+
+fn parse_env<T: std::str::FromStr>(key: &str) -> Result<Option<T>, String> {
+    match env::var(key) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("{} has an invalid value {:?}", key, value)),
+        Err(_) => Ok(None),
+    }
+}
+
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn parse_flag<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} has an invalid value {:?}", flag, value))
+}
+
+impl Config {
+    /// Loads the config from the file, environment and `args` (without the program name),
+    /// returning it along with the arguments that weren't flags.
+    pub fn load(args: &[String]) -> Result<(Self, Vec<String>), String> {
+        let mut config_path = env::var("AKSHAR_CONFIG").ok().map(PathBuf::from);
+        let mut flags = vec![];
+        let mut positional = vec![];
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg.starts_with("--") {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{} needs a value", arg))?;
+                if arg == "--config" {
+                    config_path = Some(value.into());
+                } else {
+                    flags.push((arg.as_str(), value.as_str()));
+                }
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+
+        let mut config = match config_path {
+            Some(path) => Self::from_file(&path)?,
+            None if PathBuf::from(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(&PathBuf::from(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        for (flag, value) in flags {
+            config.apply_flag(flag, value)?;
+        }
+        config.validate()?;
+        Ok((config, positional))
+    }
+
+    fn from_file(path: &PathBuf) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read config file {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    fn apply_env(&mut self) -> Result<(), String> {
+        if let Ok(host) = env::var("APP_HOST") {
+            self.server.host = host;
+        }
+        if let Some(port) = parse_env("APP_PORT")? {
+            self.server.port = port;
+        }
+        if let Some(workers) = parse_env("APP_WORKERS")? {
+            self.server.workers = Some(workers);
+        }
+        if let Ok(url) = env::var("DATABASE_URL") {
+            self.database.url = Some(url);
+        }
+        if let Ok(host) = env::var("DB_HOST_URL") {
+            self.database.host = host;
+        }
+        if let Some(port) = parse_env("DB_PORT")? {
+            self.database.port = port;
+        }
+        if let Ok(user) = env::var("DB_USER") {
+            self.database.user = user;
+        }
+        if let Ok(password) = env::var("DB_PASSWORD") {
+            self.database.password = password;
+        }
+        if let Ok(name) = env::var("DB_NAME") {
+            self.database.name = name;
+        }
+        if let Some(pool_size) = parse_env("DB_POOL_SIZE")? {
+            self.database.pool_size = pool_size;
+        }
+        if let (Ok(cert_path), Ok(key_path)) = (env::var("TLS_CERT_PATH"), env::var("TLS_KEY_PATH")) {
+            self.tls = Some(TlsConfig {
+                cert_path: cert_path.into(),
+                key_path: key_path.into(),
+            });
+        }
+        if let Ok(key) = env::var("SESSION_KEY") {
+            self.session.key = Some(key);
+        }
+        if let Ok(origins) = env::var("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = Some(parse_list(&origins));
+        }
+        if let Ok(profile) = env::var("CORS_PROFILE") {
+            self.cors.profile = match profile.as_str() {
+                "development" => CorsProfile::Development,
+                "production" => CorsProfile::Production,
+                _ => {
+                    return Err(format!(
+                        "CORS_PROFILE must be development or production, not {:?}",
+                        profile
+                    ))
+                }
+            };
+        }
+        if let Some(requests_per_minute) = parse_env("RATE_LIMIT_PER_MINUTE")? {
+            self.rate_limit.requests_per_minute = requests_per_minute;
+        }
+        if let Ok(store) = env::var("RATE_LIMIT_STORE") {
+            self.rate_limit.store = match store.as_str() {
+                "memory" => RateLimitStore::Memory,
+                "postgres" => RateLimitStore::Postgres,
+                _ => {
+                    return Err(format!(
+                        "RATE_LIMIT_STORE must be memory or postgres, not {:?}",
+                        store
+                    ))
+                }
+            };
+        }
+        if let Some(difficulty) = parse_env("SIGNUP_POW_DIFFICULTY")? {
+            self.rate_limit.signup_difficulty = difficulty;
+        }
+        if let Ok(format) = env::var("LOG_FORMAT") {
+            self.logging.format = match format.as_str() {
+                "json" => LogFormat::Json,
+                "logfmt" => LogFormat::Logfmt,
+                "pretty" => LogFormat::Pretty,
+                _ => {
+                    return Err(format!(
+                        "LOG_FORMAT must be json, logfmt or pretty, not {:?}",
+                        format
+                    ))
+                }
+            };
+        }
+        if let Ok(level) = env::var("LOG_LEVEL") {
+            self.logging.level = level;
+        }
+        if let Ok(frontend_path) = env::var("FRONTEND_PATH") {
+            self.frontend_path = frontend_path.into();
+        }
+        Ok(())
+    }
+
+    fn apply_flag(&mut self, flag: &str, value: &str) -> Result<(), String> {
+        match flag {
+            "--host" => self.server.host = value.to_string(),
+            "--port" => self.server.port = parse_flag(flag, value)?,
+            "--workers" => self.server.workers = Some(parse_flag(flag, value)?),
+            "--database-url" => self.database.url = Some(value.to_string()),
+            "--pool-size" => self.database.pool_size = parse_flag(flag, value)?,
+            "--frontend-path" => self.frontend_path = value.into(),
+            _ => return Err(format!("Unknown flag {}", flag)),
+        }
+        Ok(())
+    }
+
+    /// Checks settings that can't be expressed in their types.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.server.host.is_empty() {
+            return Err("server.host must not be empty".to_string());
+        }
+        if self.server.workers == Some(0) {
+            return Err("server.workers must be at least 1".to_string());
+        }
+        if self.database.pool_size == 0 {
+            return Err("database.pool_size must be at least 1".to_string());
+        }
+        if let Some(min_idle) = self.database.min_idle {
+            if min_idle > self.database.pool_size {
+                return Err("database.min_idle must not be larger than database.pool_size".to_string());
+            }
+        }
+        match &self.session.key {
+            Some(key) if key.len() < 32 => {
+                return Err("session.key must be at least 32 bytes long".to_string());
+            }
+            None if !cfg!(debug_assertions) => {
+                return Err("session.key (or SESSION_KEY) must be set in release builds".to_string());
+            }
+            _ => {}
+        }
+        if let Some(tls) = &self.tls {
+            for path in &[&tls.cert_path, &tls.key_path] {
+                if !path.is_file() {
+                    return Err(format!("TLS file {} does not exist", path.display()));
+                }
+            }
+        }
+        match self.session.same_site.as_deref() {
+            None | Some("strict") | Some("lax") => {}
+            Some("none") if self.session.secure => {}
+            Some("none") => {
+                return Err("session.same_site = \"none\" also needs session.secure = true".to_string());
+            }
+            Some(other) => {
+                return Err(format!("session.same_site must be strict, lax or none, not {:?}", other));
+            }
+        }
+        for origin in &self.cors.origins() {
+            if !(origin.starts_with("http://") || origin.starts_with("https://")) || origin.ends_with('/') {
+                return Err(format!(
+                    "CORS origin {} must look like https://example.com, without a trailing slash",
+                    origin
+                ));
+            }
+        }
+        for method in &self.cors.allowed_methods {
+            if Method::from_bytes(method.as_bytes()).is_err() {
+                return Err(format!("cors.allowed_methods has an invalid method {:?}", method));
+            }
+        }
+        for header in &self.cors.allowed_headers {
+            if HeaderName::from_bytes(header.as_bytes()).is_err() {
+                return Err(format!("cors.allowed_headers has an invalid header {:?}", header));
+            }
+        }
+        if let Err(error) = EnvFilter::try_new(&self.logging.level) {
+            return Err(format!("logging.level is not a valid filter: {}", error));
+        }
+        if self.rate_limit.requests_per_minute > 0 && self.rate_limit.burst == 0 {
+            return Err("rate_limit.burst must be at least 1 when rate limiting is on".to_string());
+        }
+        for (prefix, limit) in &self.rate_limit.routes {
+            if !prefix.starts_with('/') {
+                return Err(format!("rate_limit.routes prefix {:?} must start with /", prefix));
+            }
+            if limit.requests_per_minute > 0 && limit.burst == 0 {
+                return Err(format!("rate_limit.routes.{:?} needs a burst of at least 1", prefix));
+            }
+        }
+        if self.rate_limit.login_backoff_max_secs < self.rate_limit.login_backoff_secs {
+            return Err(
+                "rate_limit.login_backoff_max_secs must not be less than login_backoff_secs"
+                    .to_string(),
+            );
+        }
+        // Past this, signing up would take minutes in a browser
+        if self.rate_limit.signup_difficulty > 24 {
+            return Err("rate_limit.signup_difficulty must be at most 24".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn bind_address(&self) -> String {
+        format!("{}:{}", self.server.host, self.server.port)
+    }
+}
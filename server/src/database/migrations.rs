@@ -0,0 +1,32 @@
+use diesel::pg::PgConnection;
+use diesel_migrations::{MigrationConnection, RunMigrationsError};
+use std::io;
+
+embed_migrations!("migrations");
+
+/// The newest migration embedded in this binary.
+pub const SCHEMA_VERSION: &str = env!("LATEST_MIGRATION");
+
+/// Refuses databases migrated past `SCHEMA_VERSION`,
+/// since a newer server must have changed the schema.
+pub fn check_schema_version(conn: &PgConnection) -> Result<(), String> {
+    conn.setup().map_err(|e| e.to_string())?;
+    match conn.latest_run_migration_version().map_err(|e| e.to_string())? {
+        Some(version) if version.as_str() > SCHEMA_VERSION => Err(format!(
+            "Database schema is at version {}, but this server only knows versions up to {}",
+            version, SCHEMA_VERSION
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Whether every embedded migration has been applied.
+pub fn is_current(conn: &PgConnection) -> Result<bool, String> {
+    let latest = conn.latest_run_migration_version().map_err(|e| e.to_string())?;
+    Ok(latest.as_deref() == Some(SCHEMA_VERSION))
+}
+
+/// Applies any pending migrations, writing the name of each one to `out` as it runs.
+pub fn run(conn: &PgConnection, out: &mut dyn io::Write) -> Result<(), RunMigrationsError> {
+    embedded_migrations::run_with_output(conn, out)
+}
@@ -1,2 +1,4 @@
+pub mod migrations;
 pub mod models;
 pub mod schema;
+pub mod sql_types;
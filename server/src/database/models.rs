@@ -1,14 +1,25 @@
 use super::schema::{self, *};
-use common::problems::{Problem, ProblemType, Topic};
+use chrono::{DateTime, Utc};
+use common::{
+    problems::{NewTopic, Problem, ProblemContent, ProblemType, Topic, CONTENT_VERSION},
+    reports::{AuditEntry, Report, ReportOutcome, ReportReason, ReportStatus, Resolution},
+    user::Role,
+    vetting::{AnswerCandidate, VettedAnswer, VettingStatus},
+};
 use diesel::{prelude::*, result};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+#[derive(Clone, Serialize, Deserialize, Queryable)]
 pub struct User {
     pub id: i32,
     pub name: String,
     pub pass: String,
     pub recommended_ids: Vec<i32>,
+    pub role: Role,
+    pub warnings: i32,
+    /// Suspended users can't sign in.
+    pub suspended: bool,
 }
 
 impl User {
@@ -25,6 +36,78 @@ impl User {
         users.filter(id.eq(user_id)).get_result(conn).optional()
     }
 
+    pub fn delete_by_name(user_name: &str, conn: &PgConnection) -> Result<usize, result::Error> {
+        use schema::users::dsl::*;
+        diesel::delete(users.filter(name.eq(user_name))).execute(conn)
+    }
+
+    pub fn set_password(
+        user_name: &str,
+        new_pass: &str,
+        conn: &PgConnection,
+    ) -> Result<usize, result::Error> {
+        use schema::users::dsl::*;
+        diesel::update(users.filter(name.eq(user_name)))
+            .set(pass.eq(new_pass))
+            .execute(conn)
+    }
+
+    pub fn set_role(
+        user_name: &str,
+        new_role: Role,
+        conn: &PgConnection,
+    ) -> Result<usize, result::Error> {
+        use schema::users::dsl::*;
+        diesel::update(users.filter(name.eq(user_name)))
+            .set(role.eq(new_role))
+            .execute(conn)
+    }
+
+    pub fn set_role_by_id(
+        user_id: i32,
+        new_role: Role,
+        conn: &PgConnection,
+    ) -> Result<usize, result::Error> {
+        use schema::users::dsl::*;
+        diesel::update(users.filter(id.eq(user_id)))
+            .set(role.eq(new_role))
+            .execute(conn)
+    }
+
+    pub fn delete_by_id(user_id: i32, conn: &PgConnection) -> Result<usize, result::Error> {
+        use schema::users::dsl::*;
+        diesel::delete(users.filter(id.eq(user_id))).execute(conn)
+    }
+
+    pub fn warn(user_id: i32, conn: &PgConnection) -> Result<usize, result::Error> {
+        use schema::users::dsl::*;
+        diesel::update(users.filter(id.eq(user_id)))
+            .set(warnings.eq(warnings + 1))
+            .execute(conn)
+    }
+
+    pub fn set_suspended(
+        user_id: i32,
+        is_suspended: bool,
+        conn: &PgConnection,
+    ) -> Result<usize, result::Error> {
+        use schema::users::dsl::*;
+        diesel::update(users.filter(id.eq(user_id)))
+            .set(suspended.eq(is_suspended))
+            .execute(conn)
+    }
+
+    pub fn set_suspended_by_name(
+        user_name: &str,
+        is_suspended: bool,
+        conn: &PgConnection,
+    ) -> Result<usize, result::Error> {
+        use schema::users::dsl::*;
+        diesel::update(users.filter(name.eq(user_name)))
+            .set(suspended.eq(is_suspended))
+            .execute(conn)
+    }
+
     pub fn update_recommendations(&self, conn: &PgConnection) -> Result<(), result::Error> {
         use schema::users::dsl::*;
         diesel::update(users.filter(id.eq(self.id)))
@@ -34,7 +117,7 @@ impl User {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct SessionUser {
     pub id: i32,
     pub name: String,
@@ -51,7 +134,7 @@ impl From<User> for SessionUser {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Insertable)]
+#[derive(Clone, Serialize, Deserialize, Insertable)]
 #[table_name = "users"]
 pub struct NewUser {
     pub name: String,
@@ -74,6 +157,41 @@ impl NewUser {
     }
 }
 
+// Passwords are left out so users can be logged safely
+
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("User")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("pass", &"[redacted]")
+            .field("recommended_ids", &self.recommended_ids)
+            .field("role", &self.role)
+            .field("warnings", &self.warnings)
+            .field("suspended", &self.suspended)
+            .finish()
+    }
+}
+
+impl fmt::Debug for SessionUser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SessionUser")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("pass", &"[redacted]")
+            .finish()
+    }
+}
+
+impl fmt::Debug for NewUser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NewUser")
+            .field("name", &self.name)
+            .field("pass", &"[redacted]")
+            .finish()
+    }
+}
+
 impl From<User> for NewUser {
     fn from(user: User) -> NewUser {
         NewUser {
@@ -96,19 +214,78 @@ impl From<SessionUser> for NewUser {
 pub struct DbProblem {
     pub id: i32,
     pub owner_id: i32,
-    pub p_type: String,
-    pub topic: String,
+    pub p_type: ProblemType,
     pub tags: Vec<String>,
     pub prompt: String,
-    pub data: String,
+    pub data: serde_json::Value,
     pub recommendations: i32,
     pub explanation: String,
+    pub topic_id: i32,
+    pub content_version: i32,
+    pub hidden: bool,
 }
 
 impl DbProblem {
-    pub fn get_by_id(req_id: i32, conn: &PgConnection) -> Result<Option<Self>, result::Error> {
+    /// Gets a problem along with its topic.
+    pub fn get_by_id(
+        req_id: i32,
+        conn: &PgConnection,
+    ) -> Result<Option<(Self, DbTopic)>, result::Error> {
         use schema::problems::dsl::*;
-        problems.filter(id.eq(req_id)).get_result(conn).optional()
+        problems
+            .inner_join(schema::topics::table)
+            .filter(id.eq(req_id))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Gets a problem and locks it until the transaction ends, so its
+    /// content can be read, changed and written back without losing
+    /// another transaction's change. Rows referencing it can still be added.
+    pub fn get_for_update(req_id: i32, conn: &PgConnection) -> Result<Option<Self>, result::Error> {
+        use schema::problems::dsl::*;
+        problems
+            .filter(id.eq(req_id))
+            .for_no_key_update()
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Gets every problem along with its topic, in order of id.
+    pub fn all(conn: &PgConnection) -> Result<Vec<(Self, DbTopic)>, result::Error> {
+        use schema::problems::dsl::*;
+        problems
+            .inner_join(schema::topics::table)
+            .order(id)
+            .load(conn)
+    }
+
+    /// Recomputes every problem's recommendation count from
+    /// the users' `recommended_ids`, returning how many changed.
+    pub fn recount_recommendations(conn: &PgConnection) -> Result<usize, result::Error> {
+        diesel::sql_query(
+            "UPDATE problems SET recommendations = counts.count FROM (
+                SELECT problems.id, COUNT(users.id)::Int4 AS count
+                FROM problems LEFT JOIN users ON problems.id = ANY(users.recommended_ids)
+                GROUP BY problems.id
+            ) AS counts
+            WHERE problems.id = counts.id AND problems.recommendations != counts.count",
+        )
+        .execute(conn)
+    }
+
+    /// Rebuilds the indexes that searching and filtering problems use,
+    /// and refreshes the planner's statistics for them.
+    pub fn reindex_search(conn: &PgConnection) -> Result<(), result::Error> {
+        for statement in &[
+            "REINDEX TABLE problems",
+            "REINDEX TABLE topics",
+            "ANALYZE problems",
+            "ANALYZE topics",
+        ] {
+            diesel::sql_query(*statement).execute(conn)?;
+        }
+        Ok(())
     }
 
     pub fn update_recommendations(&self, conn: &PgConnection) -> Result<(), result::Error> {
@@ -119,16 +296,53 @@ impl DbProblem {
         Ok(())
     }
 
-    pub fn into_problem(self) -> Result<Problem, serde_json::Error> {
+    pub fn set_hidden(
+        problem_id: i32,
+        is_hidden: bool,
+        conn: &PgConnection,
+    ) -> Result<usize, result::Error> {
+        use schema::problems::dsl::*;
+        diesel::update(problems.filter(id.eq(problem_id)))
+            .set(hidden.eq(is_hidden))
+            .execute(conn)
+    }
+
+    pub fn set_tags(
+        problem_id: i32,
+        new_tags: &[String],
+        conn: &PgConnection,
+    ) -> Result<usize, result::Error> {
+        use schema::problems::dsl::*;
+        diesel::update(problems.filter(id.eq(problem_id)))
+            .set(tags.eq(new_tags))
+            .execute(conn)
+    }
+
+    /// Replaces a problem's content, storing it in the current format.
+    pub fn set_content(
+        problem_id: i32,
+        content: &ProblemContent,
+        conn: &PgConnection,
+    ) -> Result<usize, result::Error> {
+        use schema::problems::dsl::*;
+        let value = serde_json::to_value(content)
+            .map_err(|e| result::Error::SerializationError(Box::new(e)))?;
+        diesel::update(problems.filter(id.eq(problem_id)))
+            .set((data.eq(value), content_version.eq(CONTENT_VERSION)))
+            .execute(conn)
+    }
+
+    pub fn into_problem(self, topic: DbTopic) -> Result<Problem, serde_json::Error> {
         Ok(Problem {
             id: self.id,
             owner_id: self.owner_id,
             prompt: self.prompt,
             recommendations: self.recommendations,
-            topic: serde_json::from_str(&self.topic)?,
+            topic: topic.into_topic(),
             tags: self.tags,
-            content: serde_json::from_str(&self.data)?,
+            content: ProblemContent::from_stored(self.content_version, self.data)?,
             explanation: self.explanation,
+            hidden: self.hidden,
         })
     }
 }
@@ -137,12 +351,13 @@ impl DbProblem {
 #[table_name = "problems"]
 pub struct NewDbProblem {
     pub owner_id: i32,
-    pub p_type: String,
-    pub topic: String,
+    pub p_type: ProblemType,
     pub tags: Vec<String>,
     pub prompt: String,
-    pub data: String,
+    pub data: serde_json::Value,
     pub explanation: String,
+    pub topic_id: i32,
+    pub content_version: i32,
 }
 
 impl NewDbProblem {
@@ -151,12 +366,13 @@ impl NewDbProblem {
     ) -> Result<Self, serde_json::Error> {
         Ok(Self {
             owner_id: problem.owner_id,
-            p_type: serde_json::to_string(&problem.content.get_type())?,
-            topic: serde_json::to_string(&problem.topic)?,
+            p_type: problem.content.get_type(),
             tags: problem.tags,
             prompt: problem.prompt,
-            data: serde_json::to_string(&problem.content)?,
+            data: serde_json::to_value(&problem.content)?,
             explanation: problem.explanation,
+            topic_id: problem.topic_id,
+            content_version: CONTENT_VERSION,
         })
     }
 
@@ -173,7 +389,7 @@ pub struct ProblemQuery {
     #[serde(default)]
     pub owner_id: Option<i32>,
     #[serde(default)]
-    pub topic: Option<Topic>,
+    pub topic_id: Option<i32>,
     #[serde(default)]
     pub tags: Option<Vec<String>>,
     #[serde(default)]
@@ -183,26 +399,719 @@ pub struct ProblemQuery {
 }
 
 impl ProblemQuery {
-    pub fn query(&self, conn: &PgConnection) -> Result<Vec<i32>, result::Error> {
+    /// Finds matching problems, leaving out hidden ones unless `include_hidden`.
+    pub fn query(
+        &self,
+        include_hidden: bool,
+        conn: &PgConnection,
+    ) -> Result<Vec<i32>, result::Error> {
         use schema::problems::dsl::*;
         let mut query = problems.select(id).into_boxed();
+        if !include_hidden {
+            query = query.filter(hidden.eq(false));
+        }
         if let Some(p_id) = self.id {
             query = query.filter(id.eq(p_id));
         }
         if let Some(p_owner_id) = self.owner_id {
             query = query.filter(owner_id.eq(p_owner_id));
         }
-        if let Some(p_topic) = &self.topic {
-            query = query.filter(topic.eq(serde_json::to_string(p_topic).unwrap()));
+        if let Some(p_topic_id) = self.topic_id {
+            query = query.filter(topic_id.eq(p_topic_id));
         }
         if let Some(p_tags) = &self.tags {
             query = query.filter(tags.eq(p_tags));
         }
         if let Some(pr_type) = &self.problem_type {
-            query = query.filter(p_type.eq(serde_json::to_string(pr_type).unwrap()));
+            query = query.filter(p_type.eq(pr_type));
         }
         query
             .limit(self.max_results.unwrap_or(50) as i64)
             .get_results(conn)
     }
 }
+
+#[derive(Debug, Clone, Serialize, Queryable)]
+pub struct DbTopic {
+    pub id: i32,
+    pub slug: String,
+    pub name: String,
+    pub description: String,
+    pub parent_id: Option<i32>,
+    pub icon: Option<String>,
+}
+
+impl DbTopic {
+    pub fn all(conn: &PgConnection) -> Result<Vec<Self>, result::Error> {
+        use schema::topics::dsl::*;
+        topics.order(name).load(conn)
+    }
+
+    pub fn delete(topic_id: i32, conn: &PgConnection) -> Result<usize, result::Error> {
+        use schema::topics::dsl::*;
+        diesel::delete(topics.filter(id.eq(topic_id))).execute(conn)
+    }
+
+    /// Whether giving `topic_id` the parent `new_parent` would
+    /// make the topic its own ancestor.
+    pub fn would_cycle(
+        topic_id: i32,
+        new_parent: Option<i32>,
+        conn: &PgConnection,
+    ) -> Result<bool, result::Error> {
+        let all = Self::all(conn)?;
+        let mut ancestor = new_parent;
+        // Bounded by the number of topics in case the tree is already broken
+        for _ in 0..=all.len() {
+            match ancestor {
+                Some(ancestor_id) if ancestor_id == topic_id => return Ok(true),
+                Some(ancestor_id) => {
+                    ancestor = all
+                        .iter()
+                        .find(|topic| topic.id == ancestor_id)
+                        .and_then(|topic| topic.parent_id)
+                }
+                None => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn into_topic(self) -> Topic {
+        Topic {
+            id: self.id,
+            slug: self.slug,
+            name: self.name,
+            description: self.description,
+            parent_id: self.parent_id,
+            icon: self.icon,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Insertable, AsChangeset)]
+#[table_name = "topics"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct NewDbTopic {
+    pub slug: String,
+    pub name: String,
+    pub description: String,
+    pub parent_id: Option<i32>,
+    pub icon: Option<String>,
+}
+
+impl NewDbTopic {
+    pub fn from_new_topic(topic: NewTopic) -> Self {
+        Self {
+            slug: topic.slug,
+            name: topic.name,
+            description: topic.description,
+            parent_id: topic.parent_id,
+            icon: topic.icon,
+        }
+    }
+
+    pub fn insert(&self, conn: &PgConnection) -> Result<DbTopic, result::Error> {
+        use schema::topics::dsl::*;
+        diesel::insert_into(topics).values(self).get_result(conn)
+    }
+
+    pub fn update(&self, topic_id: i32, conn: &PgConnection) -> Result<Option<DbTopic>, result::Error> {
+        use schema::topics::dsl::*;
+        diesel::update(topics.filter(id.eq(topic_id)))
+            .set(self)
+            .get_result(conn)
+            .optional()
+    }
+}
+
+#[derive(Debug, Clone, QueryableByName)]
+pub struct RateLimitBucket {
+    #[sql_type = "diesel::sql_types::Float8"]
+    pub tokens: f64,
+    /// Whether this request took a token.
+    #[sql_type = "diesel::sql_types::Bool"]
+    pub allowed: bool,
+}
+
+impl RateLimitBucket {
+    /// Refills `bucket_key` by `per_second` tokens a second, up to `burst`,
+    /// and takes a token if there's a whole one, all in one statement so
+    /// concurrent requests can't both take the last token.
+    pub fn take(
+        bucket_key: &str,
+        per_second: f64,
+        burst: f64,
+        conn: &PgConnection,
+    ) -> Result<RateLimitBucket, result::Error> {
+        use diesel::sql_types::{Float8, Varchar};
+        diesel::sql_query(
+            "INSERT INTO rate_limit_buckets AS b (key, tokens, allowed, updated_at)
+            VALUES ($1, $3 - 1, true, now())
+            ON CONFLICT (key) DO UPDATE SET
+                tokens = CASE
+                    WHEN LEAST($3, b.tokens + EXTRACT(EPOCH FROM now() - b.updated_at) * $2) >= 1
+                    THEN LEAST($3, b.tokens + EXTRACT(EPOCH FROM now() - b.updated_at) * $2) - 1
+                    ELSE LEAST($3, b.tokens + EXTRACT(EPOCH FROM now() - b.updated_at) * $2)
+                END,
+                allowed = LEAST($3, b.tokens + EXTRACT(EPOCH FROM now() - b.updated_at) * $2) >= 1,
+                updated_at = now()
+            RETURNING tokens, allowed",
+        )
+        .bind::<Varchar, _>(bucket_key)
+        .bind::<Float8, _>(per_second)
+        .bind::<Float8, _>(burst)
+        .get_result(conn)
+    }
+
+    /// Deletes buckets untouched for `idle_secs`, which must be
+    /// long enough for them to have refilled.
+    pub fn prune(idle_secs: f64, conn: &PgConnection) -> Result<usize, result::Error> {
+        diesel::sql_query(
+            "DELETE FROM rate_limit_buckets WHERE updated_at < now() - make_interval(secs => $1)",
+        )
+        .bind::<diesel::sql_types::Float8, _>(idle_secs)
+        .execute(conn)
+    }
+}
+
+#[derive(Debug, Clone, QueryableByName)]
+pub struct LoginFailure {
+    #[sql_type = "diesel::sql_types::Float8"]
+    pub wait_secs: f64,
+}
+
+impl LoginFailure {
+    /// How long logins under `login_key` are refused for, if they are.
+    pub fn blocked_for(login_key: &str, conn: &PgConnection) -> Result<Option<f64>, result::Error> {
+        diesel::sql_query(
+            "SELECT EXTRACT(EPOCH FROM blocked_until - now())::Float8 AS wait_secs
+            FROM login_failures
+            WHERE name = $1 AND blocked_until > now()",
+        )
+        .bind::<diesel::sql_types::Varchar, _>(login_key)
+        .get_result::<LoginFailure>(conn)
+        .optional()
+        .map(|failure| failure.map(|failure| failure.wait_secs))
+    }
+
+    /// Blocks logins under `login_key` for `base_secs`, doubled for each earlier
+    /// failure up to `max_secs`. Failures are forgotten once logins have been
+    /// allowed again for `max_secs`.
+    pub fn record(
+        login_key: &str,
+        base_secs: f64,
+        max_secs: f64,
+        conn: &PgConnection,
+    ) -> Result<(), result::Error> {
+        use diesel::sql_types::{Float8, Varchar};
+        diesel::sql_query(
+            "INSERT INTO login_failures AS f (name, failures, blocked_until)
+            VALUES ($1, 1, now() + make_interval(secs => $2))
+            ON CONFLICT (name) DO UPDATE SET
+                failures = CASE
+                    WHEN f.blocked_until < now() - make_interval(secs => $3) THEN 1
+                    ELSE f.failures + 1
+                END,
+                blocked_until = now() + make_interval(secs => CASE
+                    WHEN f.blocked_until < now() - make_interval(secs => $3) THEN $2
+                    ELSE LEAST($2 * power(2, LEAST(f.failures, 30)), $3)
+                END)",
+        )
+        .bind::<Varchar, _>(login_key)
+        .bind::<Float8, _>(base_secs)
+        .bind::<Float8, _>(max_secs)
+        .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn clear(login_key: &str, conn: &PgConnection) -> Result<(), result::Error> {
+        use schema::login_failures::dsl::*;
+        diesel::delete(login_failures.filter(name.eq(login_key))).execute(conn)?;
+        Ok(())
+    }
+
+    /// Deletes failures that have been forgotten anyway, as in `record`.
+    pub fn prune(max_secs: f64, conn: &PgConnection) -> Result<usize, result::Error> {
+        diesel::sql_query(
+            "DELETE FROM login_failures WHERE blocked_until < now() - make_interval(secs => $1)",
+        )
+        .bind::<diesel::sql_types::Float8, _>(max_secs)
+        .execute(conn)
+    }
+}
+
+#[derive(Debug, Clone, Queryable)]
+pub struct DbReport {
+    pub id: i32,
+    pub problem_id: i32,
+    pub reporter_id: i32,
+    pub reason: ReportReason,
+    pub comment: String,
+    pub status: ReportStatus,
+    pub claimed_by: Option<i32>,
+    pub outcome: Option<ReportOutcome>,
+    pub resolution_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl DbReport {
+    pub fn get_by_id(report_id: i32, conn: &PgConnection) -> Result<Option<Self>, result::Error> {
+        use schema::reports::dsl::*;
+        reports.filter(id.eq(report_id)).get_result(conn).optional()
+    }
+
+    /// Gets a report and locks it until the transaction ends,
+    /// so two moderators can't act on it at once.
+    pub fn get_for_update(
+        report_id: i32,
+        conn: &PgConnection,
+    ) -> Result<Option<Self>, result::Error> {
+        use schema::reports::dsl::*;
+        reports
+            .filter(id.eq(report_id))
+            .for_update()
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Whether `moderator_id` may claim or resolve this report.
+    pub fn is_open_to(&self, moderator_id: i32) -> bool {
+        self.status != ReportStatus::Resolved
+            && self
+                .claimed_by
+                .map_or(true, |claimer| claimer == moderator_id)
+    }
+
+    pub fn claim(
+        report_id: i32,
+        moderator_id: i32,
+        conn: &PgConnection,
+    ) -> Result<Self, result::Error> {
+        use schema::reports::dsl::*;
+        diesel::update(reports.filter(id.eq(report_id)))
+            .set((
+                status.eq(ReportStatus::Claimed),
+                claimed_by.eq(moderator_id),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn resolve(
+        report_id: i32,
+        moderator_id: i32,
+        resolution: &Resolution,
+        conn: &PgConnection,
+    ) -> Result<Self, result::Error> {
+        use schema::reports::dsl::*;
+        diesel::update(reports.filter(id.eq(report_id)))
+            .set((
+                status.eq(ReportStatus::Resolved),
+                claimed_by.eq(moderator_id),
+                outcome.eq(resolution.outcome),
+                resolution_note.eq(&resolution.note),
+                resolved_at.eq(diesel::dsl::now),
+            ))
+            .get_result(conn)
+    }
+
+    pub fn into_report(self) -> Report {
+        Report {
+            id: self.id,
+            problem_id: self.problem_id,
+            reporter_id: self.reporter_id,
+            reason: self.reason,
+            comment: self.comment,
+            status: self.status,
+            claimed_by: self.claimed_by,
+            outcome: self.outcome,
+            resolution_note: self.resolution_note,
+            created_at: self.created_at,
+            resolved_at: self.resolved_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "reports"]
+pub struct NewDbReport {
+    pub problem_id: i32,
+    pub reporter_id: i32,
+    pub reason: ReportReason,
+    pub comment: String,
+}
+
+impl NewDbReport {
+    pub fn insert(&self, conn: &PgConnection) -> Result<DbReport, result::Error> {
+        use schema::reports::dsl::*;
+        diesel::insert_into(reports).values(self).get_result(conn)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportQuery {
+    #[serde(default)]
+    pub status: Option<ReportStatus>,
+    #[serde(default)]
+    pub reason: Option<ReportReason>,
+    #[serde(default)]
+    pub problem_id: Option<i32>,
+    #[serde(default)]
+    pub claimed_by: Option<i32>,
+    #[serde(default)]
+    pub max_results: Option<usize>,
+}
+
+impl ReportQuery {
+    /// Finds matching reports, oldest first so the queue is worked in order.
+    pub fn query(&self, conn: &PgConnection) -> Result<Vec<DbReport>, result::Error> {
+        use schema::reports::dsl::*;
+        let mut query = reports.into_boxed();
+        if let Some(r_status) = self.status {
+            query = query.filter(status.eq(r_status));
+        }
+        if let Some(r_reason) = self.reason {
+            query = query.filter(reason.eq(r_reason));
+        }
+        if let Some(r_problem_id) = self.problem_id {
+            query = query.filter(problem_id.eq(r_problem_id));
+        }
+        if let Some(r_claimed_by) = self.claimed_by {
+            query = query.filter(claimed_by.eq(r_claimed_by));
+        }
+        query
+            .order((created_at, id))
+            .limit(self.max_results.unwrap_or(50) as i64)
+            .load(conn)
+    }
+}
+
+#[derive(Debug, Clone, Queryable)]
+pub struct DbAuditEntry {
+    pub id: i32,
+    pub actor_id: i32,
+    pub action: String,
+    pub report_id: Option<i32>,
+    pub problem_id: Option<i32>,
+    pub target_user_id: Option<i32>,
+    pub detail: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl DbAuditEntry {
+    pub fn into_entry(self) -> AuditEntry {
+        AuditEntry {
+            id: self.id,
+            actor_id: self.actor_id,
+            action: self.action,
+            report_id: self.report_id,
+            problem_id: self.problem_id,
+            target_user_id: self.target_user_id,
+            detail: self.detail,
+            created_at: self.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditQuery {
+    #[serde(default)]
+    pub actor_id: Option<i32>,
+    #[serde(default)]
+    pub report_id: Option<i32>,
+    #[serde(default)]
+    pub max_results: Option<usize>,
+}
+
+impl AuditQuery {
+    /// Finds matching entries, newest first.
+    pub fn query(&self, conn: &PgConnection) -> Result<Vec<DbAuditEntry>, result::Error> {
+        use schema::audit_log::dsl::*;
+        let mut query = audit_log.into_boxed();
+        if let Some(a_actor_id) = self.actor_id {
+            query = query.filter(actor_id.eq(a_actor_id));
+        }
+        if let Some(a_report_id) = self.report_id {
+            query = query.filter(report_id.eq(a_report_id));
+        }
+        query
+            .order(id.desc())
+            .limit(self.max_results.unwrap_or(100) as i64)
+            .load(conn)
+    }
+}
+
+/// Something a moderator or admin did, to be kept in the audit log.
+#[derive(Debug, Clone, Default, Insertable)]
+#[table_name = "audit_log"]
+pub struct NewAuditEntry {
+    pub actor_id: i32,
+    pub action: String,
+    pub report_id: Option<i32>,
+    pub problem_id: Option<i32>,
+    pub target_user_id: Option<i32>,
+    pub detail: String,
+}
+
+impl NewAuditEntry {
+    pub fn new(actor_id: i32, action: &str) -> Self {
+        Self {
+            actor_id,
+            action: action.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn insert(&self, conn: &PgConnection) -> Result<(), result::Error> {
+        use schema::audit_log::dsl::*;
+        diesel::insert_into(audit_log).values(self).execute(conn)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Queryable)]
+pub struct DbAnswerCandidate {
+    pub id: i32,
+    pub problem_id: i32,
+    pub answer: String,
+    pub example: String,
+    pub status: VettingStatus,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub vetted_at: Option<DateTime<Utc>>,
+}
+
+impl DbAnswerCandidate {
+    /// Queues `user_id`'s wrong `response` under its normalized `answer`,
+    /// replacing any earlier response of theirs with the same answer.
+    /// A new candidate keeps the trimmed response as its example.
+    pub fn record(
+        candidate_problem_id: i32,
+        normalized_answer: &str,
+        submitter_id: i32,
+        raw_response: &str,
+        conn: &PgConnection,
+    ) -> Result<(), result::Error> {
+        use diesel::sql_types::{Int4, Varchar};
+        diesel::sql_query(
+            "WITH candidate AS (
+                INSERT INTO answer_candidates AS c (problem_id, answer, example)
+                VALUES ($1, $2, $5)
+                ON CONFLICT (problem_id, answer) DO UPDATE SET
+                    -- Accepted answers only fail again if the solutions have since been edited
+                    status = CASE WHEN c.status = 'accepted' THEN 'pending' ELSE c.status END,
+                    vetted_at = CASE WHEN c.status = 'accepted' THEN NULL ELSE c.vetted_at END
+                RETURNING id
+            )
+            INSERT INTO candidate_submissions (candidate_id, user_id, response)
+            SELECT id, $3, $4 FROM candidate
+            ON CONFLICT (candidate_id, user_id) DO UPDATE SET
+                response = EXCLUDED.response, correct = false, submitted_at = now()",
+        )
+        .bind::<Int4, _>(candidate_problem_id)
+        .bind::<Varchar, _>(normalized_answer)
+        .bind::<Int4, _>(submitter_id)
+        .bind::<Varchar, _>(raw_response)
+        .bind::<Varchar, _>(raw_response.trim())
+        .execute(conn)?;
+        Ok(())
+    }
+
+    pub fn get_by_id(
+        candidate_id: i32,
+        conn: &PgConnection,
+    ) -> Result<Option<Self>, result::Error> {
+        use schema::answer_candidates::dsl::*;
+        answer_candidates
+            .filter(id.eq(candidate_id))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Gets a candidate and locks it until the transaction ends.
+    pub fn get_for_update(
+        candidate_id: i32,
+        conn: &PgConnection,
+    ) -> Result<Option<Self>, result::Error> {
+        use schema::answer_candidates::dsl::*;
+        answer_candidates
+            .filter(id.eq(candidate_id))
+            .for_update()
+            .get_result(conn)
+            .optional()
+    }
+
+    pub fn set_status(
+        candidate_id: i32,
+        new_status: VettingStatus,
+        new_note: Option<&str>,
+        conn: &PgConnection,
+    ) -> Result<usize, result::Error> {
+        use schema::answer_candidates::dsl::*;
+        diesel::update(answer_candidates.filter(id.eq(candidate_id)))
+            .set((
+                status.eq(new_status),
+                note.eq(new_note),
+                vetted_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)
+    }
+}
+
+#[derive(Debug, Clone, Queryable)]
+pub struct CandidateSubmission {
+    pub candidate_id: i32,
+    pub user_id: i32,
+    pub response: String,
+    pub correct: bool,
+    pub submitted_at: DateTime<Utc>,
+}
+
+impl CandidateSubmission {
+    pub fn for_candidate(
+        req_candidate_id: i32,
+        conn: &PgConnection,
+    ) -> Result<Vec<Self>, result::Error> {
+        use schema::candidate_submissions::dsl::*;
+        candidate_submissions
+            .filter(candidate_id.eq(req_candidate_id))
+            .load(conn)
+    }
+
+    pub fn set_correct(
+        req_candidate_id: i32,
+        submitter_id: i32,
+        is_correct: bool,
+        conn: &PgConnection,
+    ) -> Result<usize, result::Error> {
+        use schema::candidate_submissions::dsl::*;
+        diesel::update(
+            candidate_submissions
+                .filter(candidate_id.eq(req_candidate_id))
+                .filter(user_id.eq(submitter_id)),
+        )
+        .set(correct.eq(is_correct))
+        .execute(conn)
+    }
+}
+
+/// A candidate along with how many people gave it, as listed for owners.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct CandidateSummary {
+    #[sql_type = "diesel::sql_types::Int4"]
+    pub id: i32,
+    #[sql_type = "diesel::sql_types::Int4"]
+    pub problem_id: i32,
+    #[sql_type = "diesel::sql_types::Varchar"]
+    pub answer: String,
+    #[sql_type = "diesel::sql_types::Varchar"]
+    pub example: String,
+    #[sql_type = "diesel::sql_types::Int8"]
+    pub submissions: i64,
+    #[sql_type = "super::sql_types::VettingStatusSql"]
+    pub status: VettingStatus,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Varchar>"]
+    pub note: Option<String>,
+    #[sql_type = "diesel::sql_types::Timestamptz"]
+    pub created_at: DateTime<Utc>,
+}
+
+impl CandidateSummary {
+    pub fn into_candidate(self) -> AnswerCandidate {
+        AnswerCandidate {
+            id: self.id,
+            problem_id: self.problem_id,
+            answer: self.answer,
+            example: self.example,
+            submissions: self.submissions,
+            status: self.status,
+            note: self.note,
+            created_at: self.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VettingQuery {
+    #[serde(default)]
+    pub problem_id: Option<i32>,
+    /// Defaults to pending.
+    #[serde(default)]
+    pub status: Option<VettingStatus>,
+    #[serde(default)]
+    pub max_results: Option<usize>,
+}
+
+impl VettingQuery {
+    /// Finds candidates for `owner`'s problems, most often given first.
+    pub fn query(
+        &self,
+        owner: i32,
+        conn: &PgConnection,
+    ) -> Result<Vec<CandidateSummary>, result::Error> {
+        use super::sql_types::VettingStatusSql;
+        use diesel::sql_types::{Int4, Int8, Nullable};
+        diesel::sql_query(
+            "SELECT c.id, c.problem_id, c.answer, c.example,
+                COUNT(s.user_id) AS submissions, c.status, c.note, c.created_at
+            FROM answer_candidates c
+            JOIN problems p ON p.id = c.problem_id
+            JOIN candidate_submissions s ON s.candidate_id = c.id
+            WHERE p.owner_id = $1 AND ($2::Int4 IS NULL OR c.problem_id = $2) AND c.status = $3
+            GROUP BY c.id
+            ORDER BY submissions DESC, c.id
+            LIMIT $4",
+        )
+        .bind::<Int4, _>(owner)
+        .bind::<Nullable<Int4>, _>(self.problem_id)
+        .bind::<VettingStatusSql, _>(self.status.unwrap_or(VettingStatus::Pending))
+        .bind::<Int8, _>(self.max_results.unwrap_or(50) as i64)
+        .load(conn)
+    }
+}
+
+#[derive(Debug, Clone, QueryableByName)]
+pub struct VettedAnswerRow {
+    #[sql_type = "diesel::sql_types::Varchar"]
+    pub response: String,
+    #[sql_type = "super::sql_types::VettingStatusSql"]
+    pub status: VettingStatus,
+    #[sql_type = "diesel::sql_types::Nullable<diesel::sql_types::Varchar>"]
+    pub note: Option<String>,
+    #[sql_type = "diesel::sql_types::Bool"]
+    pub correct: bool,
+    #[sql_type = "diesel::sql_types::Timestamptz"]
+    pub submitted_at: DateTime<Utc>,
+}
+
+impl VettedAnswerRow {
+    /// `submitter_id`'s queued answers to a problem, newest first.
+    pub fn for_submitter(
+        submitter_id: i32,
+        req_problem_id: i32,
+        conn: &PgConnection,
+    ) -> Result<Vec<Self>, result::Error> {
+        use diesel::sql_types::Int4;
+        diesel::sql_query(
+            "SELECT s.response, c.status, c.note, s.correct, s.submitted_at
+            FROM candidate_submissions s
+            JOIN answer_candidates c ON c.id = s.candidate_id
+            WHERE s.user_id = $1 AND c.problem_id = $2
+            ORDER BY s.submitted_at DESC",
+        )
+        .bind::<Int4, _>(submitter_id)
+        .bind::<Int4, _>(req_problem_id)
+        .load(conn)
+    }
+
+    pub fn into_vetted_answer(self) -> VettedAnswer {
+        VettedAnswer {
+            response: self.response,
+            status: self.status,
+            note: self.note,
+            correct: self.correct,
+            submitted_at: self.submitted_at,
+        }
+    }
+}
@@ -1,22 +1,151 @@
 table! {
-    users (id) {
+    use diesel::sql_types::*;
+    use crate::database::sql_types::*;
+
+    answer_candidates (id) {
         id -> Int4,
+        problem_id -> Int4,
+        answer -> Varchar,
+        example -> Varchar,
+        status -> VettingStatusSql,
+        note -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        vetted_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::database::sql_types::*;
+
+    audit_log (id) {
+        id -> Int4,
+        actor_id -> Int4,
+        action -> Varchar,
+        report_id -> Nullable<Int4>,
+        problem_id -> Nullable<Int4>,
+        target_user_id -> Nullable<Int4>,
+        detail -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::database::sql_types::*;
+
+    candidate_submissions (candidate_id, user_id) {
+        candidate_id -> Int4,
+        user_id -> Int4,
+        response -> Varchar,
+        correct -> Bool,
+        submitted_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::database::sql_types::*;
+
+    login_failures (name) {
         name -> Varchar,
-        pass -> Varchar,
-        recommended_ids -> Array<Int4>,
+        failures -> Int4,
+        blocked_until -> Timestamptz,
     }
 }
 
 table! {
+    use diesel::sql_types::*;
+    use crate::database::sql_types::*;
+
     problems (id) {
-        id  -> Int4,
+        id -> Int4,
         owner_id -> Int4,
-        p_type -> Varchar,
-        topic -> Varchar,
+        p_type -> ProblemTypeSql,
         tags -> Array<Varchar>,
         prompt -> Varchar,
-        data -> Varchar,
+        data -> Jsonb,
         recommendations -> Int4,
         explanation -> Varchar,
+        topic_id -> Int4,
+        content_version -> Int4,
+        hidden -> Bool,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::database::sql_types::*;
+
+    rate_limit_buckets (key) {
+        key -> Varchar,
+        tokens -> Float8,
+        allowed -> Bool,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::database::sql_types::*;
+
+    reports (id) {
+        id -> Int4,
+        problem_id -> Int4,
+        reporter_id -> Int4,
+        reason -> ReportReasonSql,
+        comment -> Varchar,
+        status -> ReportStatusSql,
+        claimed_by -> Nullable<Int4>,
+        outcome -> Nullable<ReportOutcomeSql>,
+        resolution_note -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        resolved_at -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    use diesel::sql_types::*;
+    use crate::database::sql_types::*;
+
+    topics (id) {
+        id -> Int4,
+        slug -> Varchar,
+        name -> Varchar,
+        description -> Varchar,
+        parent_id -> Nullable<Int4>,
+        icon -> Nullable<Varchar>,
     }
 }
+
+table! {
+    use diesel::sql_types::*;
+    use crate::database::sql_types::*;
+
+    users (id) {
+        id -> Int4,
+        name -> Varchar,
+        pass -> Varchar,
+        recommended_ids -> Array<Int4>,
+        role -> RoleSql,
+        warnings -> Int4,
+        suspended -> Bool,
+    }
+}
+
+joinable!(answer_candidates -> problems (problem_id));
+joinable!(candidate_submissions -> answer_candidates (candidate_id));
+joinable!(problems -> topics (topic_id));
+joinable!(reports -> problems (problem_id));
+
+allow_tables_to_appear_in_same_query!(
+    answer_candidates,
+    audit_log,
+    candidate_submissions,
+    login_failures,
+    problems,
+    rate_limit_buckets,
+    reports,
+    topics,
+    users,
+);
@@ -0,0 +1,172 @@
+use common::{
+    problems::ProblemType,
+    reports::{ReportOutcome, ReportReason, ReportStatus},
+    user::Role,
+    vetting::VettingStatus,
+};
+use diesel::{
+    deserialize::{self, FromSql, FromSqlRow, Queryable},
+    expression::{bound::Bound, AsExpression},
+    pg::Pg,
+    row::Row,
+    serialize::{self, IsNull, Output, ToSql},
+    sql_types::Nullable,
+};
+use std::io::Write;
+
+/// The `problem_type` Postgres enum.
+#[derive(SqlType, QueryId)]
+#[postgres(type_name = "problem_type")]
+pub struct ProblemTypeSql;
+
+/// The `user_role` Postgres enum.
+#[derive(SqlType, QueryId)]
+#[postgres(type_name = "user_role")]
+pub struct RoleSql;
+
+/// The `report_reason` Postgres enum.
+#[derive(SqlType, QueryId)]
+#[postgres(type_name = "report_reason")]
+pub struct ReportReasonSql;
+
+/// The `report_status` Postgres enum.
+#[derive(SqlType, QueryId)]
+#[postgres(type_name = "report_status")]
+pub struct ReportStatusSql;
+
+/// The `report_outcome` Postgres enum.
+#[derive(SqlType, QueryId)]
+#[postgres(type_name = "report_outcome")]
+pub struct ReportOutcomeSql;
+
+/// The `vetting_status` Postgres enum.
+#[derive(SqlType, QueryId)]
+#[postgres(type_name = "vetting_status")]
+pub struct VettingStatusSql;
+
+/// The enum label a problem type is stored under.
+fn label(p_type: &ProblemType) -> &'static str {
+    match p_type {
+        ProblemType::FreeResponse => "FreeResponse",
+        ProblemType::MultipleChoice => "MultipleChoice",
+        ProblemType::Checklist => "Checklist",
+        ProblemType::Ordering => "Ordering",
+        ProblemType::Matching => "Matching",
+        ProblemType::MultiPart => "MultiPart",
+        ProblemType::Template => "Template",
+        ProblemType::Cloze => "Cloze",
+        ProblemType::Grid => "Grid",
+        ProblemType::Translation => "Translation",
+    }
+}
+
+fn from_label(label: &[u8]) -> Option<ProblemType> {
+    Some(match label {
+        b"FreeResponse" => ProblemType::FreeResponse,
+        b"MultipleChoice" => ProblemType::MultipleChoice,
+        b"Checklist" => ProblemType::Checklist,
+        b"Ordering" => ProblemType::Ordering,
+        b"Matching" => ProblemType::Matching,
+        b"MultiPart" => ProblemType::MultiPart,
+        b"Template" => ProblemType::Template,
+        b"Cloze" => ProblemType::Cloze,
+        b"Grid" => ProblemType::Grid,
+        b"Translation" => ProblemType::Translation,
+        _ => return None,
+    })
+}
+
+// These enums live in `common`, which doesn't know about diesel,
+// so these are written out instead of derived.
+
+impl ToSql<ProblemTypeSql, Pg> for ProblemType {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+        out.write_all(label(self).as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<ProblemTypeSql, Pg> for ProblemType {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        let bytes = not_none!(bytes);
+        from_label(bytes).ok_or_else(|| {
+            format!("Unrecognized problem type {}", String::from_utf8_lossy(bytes)).into()
+        })
+    }
+}
+
+/// For enums whose `Display` and `FromStr` use the Postgres labels.
+macro_rules! labelled_sql {
+    ($rust:ty, $sql:ty) => {
+        impl ToSql<$sql, Pg> for $rust {
+            fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+                write!(out, "{}", self)?;
+                Ok(IsNull::No)
+            }
+        }
+
+        impl FromSql<$sql, Pg> for $rust {
+            fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+                let label = std::str::from_utf8(not_none!(bytes))?;
+                label.parse().map_err(|e: String| e.into())
+            }
+        }
+    };
+}
+
+labelled_sql!(Role, RoleSql);
+labelled_sql!(ReportReason, ReportReasonSql);
+labelled_sql!(ReportStatus, ReportStatusSql);
+labelled_sql!(ReportOutcome, ReportOutcomeSql);
+labelled_sql!(VettingStatus, VettingStatusSql);
+
+/// The rest is the same for every enum.
+macro_rules! enum_expression {
+    ($rust:ty, $sql:ty) => {
+        impl FromSqlRow<$sql, Pg> for $rust {
+            fn build_from_row<R: Row<Pg>>(row: &mut R) -> deserialize::Result<Self> {
+                FromSql::<$sql, Pg>::from_sql(row.take())
+            }
+        }
+
+        impl Queryable<$sql, Pg> for $rust {
+            type Row = Self;
+
+            fn build(row: Self::Row) -> Self {
+                row
+            }
+        }
+
+        impl AsExpression<$sql> for $rust {
+            type Expression = Bound<$sql, Self>;
+
+            fn as_expression(self) -> Self::Expression {
+                Bound::new(self)
+            }
+        }
+
+        impl<'a> AsExpression<$sql> for &'a $rust {
+            type Expression = Bound<$sql, Self>;
+
+            fn as_expression(self) -> Self::Expression {
+                Bound::new(self)
+            }
+        }
+
+        // For setting nullable columns without wrapping values in `Some`
+        impl AsExpression<Nullable<$sql>> for $rust {
+            type Expression = Bound<Nullable<$sql>, Self>;
+
+            fn as_expression(self) -> Self::Expression {
+                Bound::new(self)
+            }
+        }
+    };
+}
+
+enum_expression!(ProblemType, ProblemTypeSql);
+enum_expression!(Role, RoleSql);
+enum_expression!(ReportReason, ReportReasonSql);
+enum_expression!(ReportStatus, ReportStatusSql);
+enum_expression!(ReportOutcome, ReportOutcomeSql);
+enum_expression!(VettingStatus, VettingStatusSql);
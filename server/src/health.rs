@@ -0,0 +1,43 @@
+use actix_web::{error::BlockingError, web, HttpResponse};
+use akshar_owo::{database::migrations, metrics};
+use std::time::Duration;
+
+use crate::DbPool;
+
+/// How long `/readyz` waits for a database connection before reporting unready.
+const READY_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.route("/healthz", web::get().to(healthz))
+        .route("/readyz", web::get().to(readyz))
+        .route("/metrics", web::get().to(render_metrics));
+}
+
+async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().body("ok")
+}
+
+async fn readyz(pool: web::Data<DbPool>) -> HttpResponse {
+    let ready = metrics::block(move || -> Result<(), String> {
+        let conn = pool
+            .get_timeout(READY_TIMEOUT)
+            .map_err(|e| format!("Database unreachable: {}", e))?;
+        if migrations::is_current(&conn)? {
+            Ok(())
+        } else {
+            Err("Database migrations are not current".to_string())
+        }
+    })
+    .await;
+    match ready {
+        Ok(()) => HttpResponse::Ok().body("ready"),
+        Err(BlockingError::Error(error)) => HttpResponse::ServiceUnavailable().body(error),
+        Err(BlockingError::Canceled) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+async fn render_metrics(pool: web::Data<DbPool>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics::render(pool.state()))
+}
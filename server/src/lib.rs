@@ -0,0 +1,17 @@
+#[macro_use]
+extern crate diesel;
+
+#[macro_use]
+extern crate diesel_migrations;
+
+#[macro_use]
+extern crate lazy_static;
+
+#[macro_use]
+extern crate prometheus;
+
+pub mod config;
+pub mod database;
+pub mod logging;
+pub mod metrics;
+pub mod rate_limit;
@@ -0,0 +1,159 @@
+//! Structured logging, with a span per request carrying its request ID.
+//!
+//! Log only what's needed to follow a request: never request bodies,
+//! headers (which hold the session cookie) or session contents.
+
+use crate::config::{LogFormat, LoggingConfig};
+use actix_web::dev::ServiceRequest;
+use std::fmt;
+use tracing::{
+    field::{Field, Visit},
+    info_span, Event, Span, Subscriber,
+};
+use tracing_subscriber::{
+    fmt::{
+        time::{ChronoUtc, FormatTime},
+        FmtContext, FormatEvent, FormatFields, FormattedFields,
+    },
+    registry::LookupSpan,
+    EnvFilter,
+};
+
+/// The header a request ID is read from and returned in.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Installs the global subscriber. `RUST_LOG` takes precedence over `config.level`.
+pub fn init(config: &LoggingConfig) -> Result<(), String> {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&config.level))
+        .map_err(|e| e.to_string())?;
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    match config.format {
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(false)
+            .with_span_list(true)
+            .try_init(),
+        LogFormat::Logfmt => builder.event_format(Logfmt).try_init(),
+        LogFormat::Pretty => builder.try_init(),
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Whether a request ID sent by a proxy can be trusted in logs and headers.
+fn valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 64
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Picks the request's ID, reusing a valid one from a proxy, and opens its span.
+pub fn request_span(req: &ServiceRequest) -> (String, Span) {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let span = info_span!(
+        "request",
+        request_id = request_id.as_str(),
+        method = req.method().as_str(),
+        path = req.path(),
+    );
+    (request_id, span)
+}
+
+/// Formats events as `key=value` pairs, followed by the fields of each enclosing span.
+struct Logfmt;
+
+struct LogfmtVisitor<'a> {
+    writer: &'a mut dyn fmt::Write,
+    result: fmt::Result,
+}
+
+impl LogfmtVisitor<'_> {
+    fn key(field: &Field) -> &'static str {
+        match field.name() {
+            "message" => "msg",
+            name => name,
+        }
+    }
+}
+
+impl Visit for LogfmtVisitor<'_> {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        if self.result.is_ok() {
+            self.result = write!(self.writer, " {}={}", Self::key(field), value);
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if self.result.is_ok() {
+            self.result = write!(self.writer, " {}={}", Self::key(field), value);
+        }
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        if self.result.is_ok() {
+            self.result = write!(self.writer, " {}={}", Self::key(field), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if self.result.is_ok() {
+            self.result = write!(self.writer, " {}={:?}", Self::key(field), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if self.result.is_ok() {
+            self.result = write!(
+                self.writer,
+                " {}={:?}",
+                Self::key(field),
+                format!("{:?}", value)
+            );
+        }
+    }
+}
+
+impl<S, N> FormatEvent<S, N> for Logfmt
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        writer: &mut dyn fmt::Write,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        write!(writer, "ts=")?;
+        ChronoUtc::rfc3339().format_time(writer)?;
+        write!(
+            writer,
+            " level={} target={}",
+            metadata.level().to_string().to_lowercase(),
+            metadata.target()
+        )?;
+        let mut visitor = LogfmtVisitor {
+            writer: &mut *writer,
+            result: Ok(()),
+        };
+        event.record(&mut visitor);
+        visitor.result?;
+        ctx.visit_spans(|span| {
+            let extensions = span.extensions();
+            match extensions.get::<FormattedFields<N>>() {
+                Some(fields) if !fields.is_empty() => write!(writer, " {}", fields),
+                _ => Ok(()),
+            }
+        })?;
+        writeln!(writer)
+    }
+}
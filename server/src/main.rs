@@ -1,123 +1,250 @@
-#[macro_use]
-extern crate diesel;
-
-#[macro_use]
-extern crate lazy_static;
-
 mod api;
-mod database;
+mod health;
 
-use std::{env, path::PathBuf};
+use std::{fs::File, io, io::BufReader, process, time::Instant};
 
-use actix_cors::Cors;
+use actix_cors::{Cors, CorsFactory};
 use actix_files::{Files, NamedFile};
 use actix_session::{CookieSession, Session};
-use actix_web::{http, middleware, web, App, Error, HttpRequest, HttpResponse, HttpServer, Result};
-use database::models;
+use actix_web::{
+    cookie::SameSite,
+    dev::Service,
+    http::{self, HeaderName, HeaderValue},
+    middleware, web, App, Error, HttpResponse, HttpServer, Result,
+};
+use akshar_owo::{
+    config::{Config, CorsConfig, TlsConfig},
+    database::{self, migrations, models},
+    logging, metrics,
+    rate_limit::{RateLimit, RateLimiter},
+};
+use common::user::{Permission, Role};
 use diesel::{
     pg::PgConnection,
     r2d2::{self, ConnectionManager},
 };
-use env_logger::Env;
-
-fn get_env_with_dev_default(key: &'static str, default: &'static str) -> String {
-    env::var(key)
-        .map_or_else(
-            |_| {
-                if cfg!(debug_assertions) {
-                    Some(default.to_string())
-                } else {
-                    None
-                }
-            },
-            Option::Some,
-        )
-        .expect(format!("Expected {} to be set", key).as_str())
-}
-
-lazy_static! {
-    static ref FRONTEND_PATH: PathBuf = env::var("FRONTEND_PATH")
-        .unwrap_or("../client/dist".to_string())
-        .into();
-    static ref APP_HOST: String = get_env_with_dev_default("APP_HOST", "localhost");
-    static ref APP_PORT: String = get_env_with_dev_default("APP_PORT", "8080");
-    static ref DB_HOST: String = get_env_with_dev_default("DB_HOST_URL", "localhost");
-    static ref DB_PORT: String = get_env_with_dev_default("DB_PORT", "5432");
-    static ref DB_USER: String = get_env_with_dev_default("DB_USER", "postgres");
-    static ref DB_PASSWORD: String = get_env_with_dev_default("DB_PASSWORD", "postgres");
-    static ref DB_NAME: String = get_env_with_dev_default("DB_NAME", "akshardb");
-    static ref APP_ADDR: String = format!("{}:{}", *APP_HOST, *APP_PORT);
-    static ref DB_URL: String = format!(
-        "host={} port={} user={} password={} dbname={}",
-        *DB_HOST, *DB_PORT, *DB_USER, *DB_PASSWORD, *DB_NAME
-    );
-}
+use rustls::internal::pemfile;
+use tracing::{error, info};
+use tracing_futures::Instrument;
 
 type DbPool = r2d2::Pool<ConnectionManager<PgConnection>>;
 
+/// The signed in user's role, or `None` if the session isn't valid or the
+/// user is suspended. Read from the database every time, so role changes
+/// and suspensions apply straight away.
 // TODO: Convert this into middleware.
-pub async fn validate(session: &Session, pool: web::Data<DbPool>) -> Result<bool, Error> {
+pub async fn session_role(
+    session: &Session,
+    pool: web::Data<DbPool>,
+) -> Result<Option<Role>, Error> {
     let conn = pool.get().expect("couldn't get db connection from pool");
     let user = session.get::<models::SessionUser>("user")?;
     if let Some(user) = user {
-        let valid = web::block(move || -> Result<bool, diesel::result::Error> {
+        let role = metrics::block(move || -> Result<Option<Role>, diesel::result::Error> {
             let user: models::NewUser = user.into();
-            Ok(user.get(&conn)?.is_some())
+            Ok(user
+                .get(&conn)?
+                .filter(|user| !user.suspended)
+                .map(|user| user.role))
         })
         .await
         .map_err(|e| {
-            eprintln!("{}", e);
+            error!("{}", e);
             HttpResponse::InternalServerError().finish()
         })?;
-        Ok(valid)
+        Ok(role)
     } else {
-        Ok(false)
+        Ok(None)
     }
 }
 
-async fn index(_: HttpRequest) -> Result<NamedFile> {
-    Ok(NamedFile::open(FRONTEND_PATH.join("index.html"))?)
+pub async fn validate(session: &Session, pool: web::Data<DbPool>) -> Result<bool, Error> {
+    Ok(session_role(session, pool).await?.is_some())
+}
+
+/// Like `validate`, but also requires the user's role to grant `permission`.
+pub async fn authorize(
+    session: &Session,
+    pool: web::Data<DbPool>,
+    permission: Permission,
+) -> Result<bool, Error> {
+    Ok(session_role(session, pool)
+        .await?
+        .map_or(false, |role| role.can(permission)))
+}
+
+async fn index(config: web::Data<Config>) -> Result<NamedFile> {
+    Ok(NamedFile::open(config.frontend_path.join("index.html"))?)
+}
+
+fn cors(config: &CorsConfig) -> CorsFactory {
+    let mut cors = Cors::new()
+        .allowed_methods(config.allowed_methods.iter().map(String::as_str))
+        .allowed_headers(config.allowed_headers.iter().map(String::as_str))
+        // So the client can say how long to wait after a 429
+        .expose_headers(vec![http::header::RETRY_AFTER]);
+    for origin in config.origins() {
+        cors = cors.allowed_origin(&origin);
+    }
+    if config.credentials() {
+        cors = cors.supports_credentials();
+    }
+    if let Some(max_age) = config.max_age_secs {
+        cors = cors.max_age(max_age);
+    }
+    cors.finish()
+}
+
+fn load_tls(tls: &TlsConfig) -> io::Result<rustls::ServerConfig> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let certs = pemfile::certs(&mut BufReader::new(File::open(&tls.cert_path)?))
+        .map_err(|_| invalid("Could not parse the TLS certificate"))?;
+    let mut keys = pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(&tls.key_path)?))
+        .map_err(|_| invalid("Could not parse the TLS key"))?;
+    if keys.is_empty() {
+        return Err(invalid("The TLS key file has no PKCS #8 private key"));
+    }
+    let mut server_config = rustls::ServerConfig::new(rustls::NoClientAuth::new());
+    server_config
+        .set_single_cert(certs, keys.remove(0))
+        .map_err(|e| invalid(&e.to_string()))?;
+    Ok(server_config)
 }
 
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (config, commands) = Config::load(&args).unwrap_or_else(|error| {
+        eprintln!("Invalid configuration: {}", error);
+        process::exit(1);
+    });
+    if let Err(error) = logging::init(&config.logging) {
+        eprintln!("Could not set up logging: {}", error);
+        process::exit(1);
+    }
+    let migrate_only = match commands.first().map(String::as_str) {
+        None => false,
+        Some("migrate") => true,
+        Some(command) => {
+            eprintln!("Unknown command {} (expected nothing or migrate)", command);
+            process::exit(2);
+        }
+    };
+
     // Setup DB stuff
-    let manager = ConnectionManager::<PgConnection>::new(DB_URL.as_str());
+    let manager = ConnectionManager::<PgConnection>::new(config.database.url());
     let pool = r2d2::Pool::builder()
+        .max_size(config.database.pool_size)
+        .min_idle(config.database.min_idle)
+        .connection_timeout(config.database.connection_timeout())
+        .event_handler(Box::new(metrics::PoolEventHandler))
         .build(manager)
         .expect("Failed to create pool");
 
-    // Set up logger
-    env_logger::from_env(Env::default().default_filter_or("info")).init();
+    // Bring the schema up to date before serving anything
+    {
+        let conn = pool.get().expect("couldn't get db connection from pool");
+        migrations::check_schema_version(&conn).expect("Refusing to start");
+        let mut output = Vec::new();
+        let result = migrations::run(&conn, &mut output);
+        for line in String::from_utf8_lossy(&output).lines() {
+            info!("{}", line.trim());
+        }
+        result.expect("Failed to run migrations");
+    }
+    if migrate_only {
+        return Ok(());
+    }
+
+    // Shared by every worker, unlike anything made in the closure below
+    let limiter = web::Data::new(RateLimiter::new(&config.rate_limit, pool.clone()));
 
     // Run the server
-    HttpServer::new(move || {
+    let app_config = config.clone();
+    let mut server = HttpServer::new(move || {
+        let config = &app_config;
+        let mut session = CookieSession::signed(&config.session.key_bytes())
+            .name(config.session.cookie_name.as_str())
+            .domain(
+                config
+                    .session
+                    .domain
+                    .clone()
+                    .unwrap_or_else(|| config.server.host.clone()),
+            )
+            .path("/")
+            .secure(config.session.secure);
+        if let Some(max_age) = config.session.max_age_secs {
+            session = session.max_age(max_age);
+        }
+        match config.session.same_site.as_deref() {
+            Some("strict") => session = session.same_site(SameSite::Strict),
+            Some("lax") => session = session.same_site(SameSite::Lax),
+            Some("none") => session = session.same_site(SameSite::None),
+            _ => {}
+        }
+        // Without any origins, Cors would allow all of them
+        let cors_enabled = !config.cors.origins().is_empty();
         App::new()
             .data(pool.clone())
-            .wrap(middleware::Logger::default())
-            .wrap(
-                CookieSession::signed(&[0; 32])
-                    .name("actix-session")
-                    .domain(APP_HOST.clone())
-                    .path("/")
-                    .secure(false),
-            )
-            // .wrap(
-            //     // TODO: Use separate CORS headers for debug/release
-            //     Cors::new()
-            //         .allowed_origin("http://localhost:8000")
-            //         .allowed_methods(vec!["GET", "POST", "OPTIONS"])
-            //         .allowed_header(http::header::CONTENT_TYPE)
-            //         .finish(),
-            // )
+            .data(config.clone())
+            .app_data(limiter.clone())
+            .wrap(RateLimit(limiter.clone().into_inner()))
+            // Outside the rate limit, so refused requests are counted too
+            .wrap_fn(|req, srv| {
+                let start = Instant::now();
+                let method = req.method().to_string();
+                let response = srv.call(req);
+                async move {
+                    let response = response.await?;
+                    metrics::observe_request(
+                        &method,
+                        response.request().match_pattern(),
+                        response.status(),
+                        start.elapsed(),
+                    );
+                    Ok(response)
+                }
+            })
+            .wrap(session)
+            .wrap(middleware::Condition::new(cors_enabled, cors(&config.cors)))
+            .wrap_fn(|req, srv| {
+                // Outermost, so everything below logs inside the request's span
+                let (request_id, span) = logging::request_span(&req);
+                let start = Instant::now();
+                let response = srv.call(req).instrument(span.clone());
+                async move {
+                    let mut response = response.await?;
+                    span.in_scope(|| {
+                        info!(
+                            status = response.status().as_u16(),
+                            latency_ms = start.elapsed().as_millis() as u64,
+                            "request finished"
+                        )
+                    });
+                    if let Ok(value) = HeaderValue::from_str(&request_id) {
+                        response.headers_mut().insert(
+                            HeaderName::from_static(logging::REQUEST_ID_HEADER),
+                            value,
+                        );
+                    }
+                    Ok(response)
+                }
+            })
+            .configure(health::config)
             .service(web::scope("/api").configure(api::config))
             .service(
-                Files::new("", FRONTEND_PATH.clone())
+                Files::new("", config.frontend_path.clone())
                     .index_file("index.html")
                     .default_handler(web::get().to(index)),
             )
-    })
-    .bind(APP_ADDR.clone())?
-    .run()
-    .await
+    });
+    if let Some(workers) = config.server.workers {
+        server = server.workers(workers);
+    }
+    server = match &config.tls {
+        Some(tls) => server.bind_rustls(config.bind_address(), load_tls(tls)?)?,
+        None => server.bind(config.bind_address())?,
+    };
+    server.run().await
 }
@@ -0,0 +1,149 @@
+//! Prometheus metrics, served at `/metrics`.
+
+use actix_web::{error::BlockingError, http::StatusCode, web};
+use diesel::r2d2::{
+    event::{CheckoutEvent, TimeoutEvent},
+    HandleEvent, State,
+};
+use prometheus::{
+    Encoder, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, TextEncoder,
+};
+use std::{fmt::Debug, time::Duration};
+use tracing::Span;
+
+lazy_static! {
+    pub static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "http_requests_total",
+        "HTTP requests handled, by route and status",
+        &["method", "route", "status"]
+    )
+    .unwrap();
+    pub static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "http_request_duration_seconds",
+        "Time taken to handle HTTP requests, by route and status",
+        &["method", "route", "status"]
+    )
+    .unwrap();
+    pub static ref DB_POOL_CONNECTIONS: IntGauge = register_int_gauge!(
+        "db_pool_connections",
+        "Connections held by the database pool"
+    )
+    .unwrap();
+    pub static ref DB_POOL_IDLE_CONNECTIONS: IntGauge = register_int_gauge!(
+        "db_pool_idle_connections",
+        "Connections in the database pool that aren't checked out"
+    )
+    .unwrap();
+    pub static ref DB_POOL_WAIT: Histogram = register_histogram!(
+        "db_pool_wait_seconds",
+        "Time spent waiting to check a connection out of the pool"
+    )
+    .unwrap();
+    pub static ref DB_POOL_TIMEOUTS: IntCounter = register_int_counter!(
+        "db_pool_timeouts_total",
+        "Checkouts that gave up waiting for a connection"
+    )
+    .unwrap();
+    pub static ref BLOCKING_QUEUE_DEPTH: IntGauge = register_int_gauge!(
+        "blocking_queue_depth",
+        "Blocking tasks waiting for or running on the thread pool"
+    )
+    .unwrap();
+    pub static ref PROBLEMS_CREATED: IntCounter =
+        register_int_counter!("problems_created_total", "Problems created").unwrap();
+    pub static ref SUBMISSIONS: IntCounterVec = register_int_counter_vec!(
+        "submissions_total",
+        "Submissions graded, by result",
+        &["result"]
+    )
+    .unwrap();
+    pub static ref RATE_LIMITED: IntCounterVec = register_int_counter_vec!(
+        "rate_limited_total",
+        "Requests refused for going over a rate limit, by route prefix",
+        &["route"]
+    )
+    .unwrap();
+    pub static ref RECOMMENDATIONS: IntCounterVec = register_int_counter_vec!(
+        "recommendations_total",
+        "Problems recommended or unrecommended",
+        &["action"]
+    )
+    .unwrap();
+    pub static ref REPORTS: IntCounterVec = register_int_counter_vec!(
+        "reports_total",
+        "Problem reports filed, by reason",
+        &["reason"]
+    )
+    .unwrap();
+    pub static ref REPORTS_RESOLVED: IntCounterVec = register_int_counter_vec!(
+        "reports_resolved_total",
+        "Problem reports resolved, by outcome",
+        &["outcome"]
+    )
+    .unwrap();
+}
+
+pub fn observe_request(method: &str, route: Option<String>, status: StatusCode, elapsed: Duration) {
+    // Label by route pattern rather than path to keep the number of series bounded
+    let route = route.unwrap_or_else(|| "unmatched".to_string());
+    let status = status.as_u16().to_string();
+    let labels = [method, route.as_str(), status.as_str()];
+    HTTP_REQUESTS.with_label_values(&labels).inc();
+    HTTP_REQUEST_DURATION
+        .with_label_values(&labels)
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Records pool checkouts for the pool metrics.
+#[derive(Debug)]
+pub struct PoolEventHandler;
+
+impl HandleEvent for PoolEventHandler {
+    fn handle_checkout(&self, event: CheckoutEvent) {
+        DB_POOL_WAIT.observe(event.duration().as_secs_f64());
+    }
+
+    fn handle_timeout(&self, _event: TimeoutEvent) {
+        DB_POOL_TIMEOUTS.inc();
+    }
+}
+
+/// Decrements the queue depth even if the blocking future is dropped early.
+struct QueueGuard;
+
+impl QueueGuard {
+    fn new() -> Self {
+        BLOCKING_QUEUE_DEPTH.inc();
+        QueueGuard
+    }
+}
+
+impl Drop for QueueGuard {
+    fn drop(&mut self) {
+        BLOCKING_QUEUE_DEPTH.dec();
+    }
+}
+
+/// `web::block`, counted in the blocking queue depth. `f` runs
+/// inside the caller's span so its logs keep the request ID.
+pub async fn block<F, I, E>(f: F) -> Result<I, BlockingError<E>>
+where
+    F: FnOnce() -> Result<I, E> + Send + 'static,
+    I: Send + 'static,
+    E: Send + Debug + 'static,
+{
+    let _guard = QueueGuard::new();
+    let span = Span::current();
+    web::block(move || span.in_scope(f)).await
+}
+
+/// Renders every metric in the Prometheus text format.
+pub fn render(pool_state: State) -> String {
+    DB_POOL_CONNECTIONS.set(pool_state.connections as i64);
+    DB_POOL_IDLE_CONNECTIONS.set(pool_state.idle_connections as i64);
+    let mut buffer = vec![];
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .expect("Failed to encode metrics");
+    String::from_utf8(buffer).expect("Metrics should be UTF-8")
+}
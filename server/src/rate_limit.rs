@@ -0,0 +1,384 @@
+//! Token bucket rate limiting, and backoff for failed logins.
+
+use crate::{
+    config::{RateLimitConfig, RateLimitStore, RouteLimit},
+    database::models,
+    metrics,
+};
+use actix_session::UserSession;
+use actix_web::{
+    dev::{ConnectionInfo, Service, ServiceRequest, ServiceResponse, Transform},
+    error::BlockingError,
+    http::header::RETRY_AFTER,
+    Error, HttpRequest, HttpResponse,
+};
+use diesel::{
+    pg::PgConnection,
+    r2d2::{ConnectionManager, Pool},
+};
+use futures::future::{ok, LocalBoxFuture, Ready};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    net::SocketAddr,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tracing::{error, info};
+
+type DbPool = Pool<ConnectionManager<PgConnection>>;
+
+/// How many buckets are taken from between sweeps for idle ones.
+const PRUNE_EVERY: usize = 1000;
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+struct LoginFailures {
+    failures: u32,
+    blocked_until: Instant,
+}
+
+#[derive(Default)]
+struct MemoryStore {
+    buckets: HashMap<String, Bucket>,
+    logins: HashMap<String, LoginFailures>,
+}
+
+enum Store {
+    Memory(Mutex<MemoryStore>),
+    Postgres(DbPool),
+}
+
+/// Shared by every worker, so limits hold across them.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    store: Store,
+    takes: AtomicUsize,
+    /// Long enough for any bucket to refill, after which it can be forgotten.
+    idle_secs: f64,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig, pool: DbPool) -> Self {
+        let store = match config.store {
+            RateLimitStore::Memory => Store::Memory(Mutex::new(MemoryStore::default())),
+            RateLimitStore::Postgres => Store::Postgres(pool),
+        };
+        let default = RouteLimit {
+            requests_per_minute: config.requests_per_minute,
+            burst: config.burst,
+        };
+        let idle_secs = config
+            .routes
+            .values()
+            .chain(Some(&default))
+            .filter(|limit| limit.requests_per_minute > 0)
+            .map(|limit| f64::from(limit.burst) * 60.0 / f64::from(limit.requests_per_minute))
+            .fold(0.0, f64::max);
+        Self {
+            config: config.clone(),
+            store,
+            takes: AtomicUsize::new(0),
+            idle_secs,
+        }
+    }
+
+    /// The limit on `path` and the prefix it's kept under, if it's limited.
+    fn limit_for(&self, path: &str) -> Option<(&str, RouteLimit)> {
+        let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let matching = self
+            .config
+            .routes
+            .iter()
+            .filter(|(prefix, _)| {
+                let prefix: Vec<&str> = prefix.split('/').filter(|s| !s.is_empty()).collect();
+                prefix.len() <= path.len()
+                    && prefix
+                        .iter()
+                        .zip(&path)
+                        .all(|(expected, actual)| *expected == "*" || expected == actual)
+            })
+            .max_by_key(|(prefix, _)| prefix.split('/').filter(|s| !s.is_empty()).count());
+        let (prefix, limit) = match matching {
+            Some((prefix, limit)) => (prefix.as_str(), *limit),
+            None => (
+                "*",
+                RouteLimit {
+                    requests_per_minute: self.config.requests_per_minute,
+                    burst: self.config.burst,
+                },
+            ),
+        };
+        if limit.requests_per_minute == 0 {
+            None
+        } else {
+            Some((prefix, limit))
+        }
+    }
+
+    /// Takes a token from `key`'s bucket, returning how long
+    /// until one is available if the bucket is empty.
+    pub fn take(&self, key: &str, limit: RouteLimit) -> Result<Option<Duration>, String> {
+        let per_second = f64::from(limit.requests_per_minute) / 60.0;
+        let burst = f64::from(limit.burst);
+        if self.takes.fetch_add(1, Ordering::Relaxed) % PRUNE_EVERY == PRUNE_EVERY - 1 {
+            self.prune()?;
+        }
+        let (tokens, allowed) = match &self.store {
+            Store::Memory(store) => {
+                let mut store = store.lock().unwrap();
+                let now = Instant::now();
+                let bucket = store.buckets.entry(key.to_string()).or_insert(Bucket {
+                    tokens: burst,
+                    updated: now,
+                });
+                let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+                bucket.tokens = burst.min(bucket.tokens + elapsed * per_second);
+                bucket.updated = now;
+                let allowed = bucket.tokens >= 1.0;
+                if allowed {
+                    bucket.tokens -= 1.0;
+                }
+                (bucket.tokens, allowed)
+            }
+            Store::Postgres(pool) => {
+                let conn = pool.get().map_err(|e| e.to_string())?;
+                let bucket = models::RateLimitBucket::take(key, per_second, burst, &conn)
+                    .map_err(|e| e.to_string())?;
+                (bucket.tokens, bucket.allowed)
+            }
+        };
+        Ok(if allowed {
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - tokens) / per_second))
+        })
+    }
+
+    /// Forgets buckets that have refilled and login failures that have expired.
+    fn prune(&self) -> Result<(), String> {
+        let max_secs = self.config.login_backoff_max_secs;
+        match &self.store {
+            Store::Memory(store) => {
+                let mut store = store.lock().unwrap();
+                let idle = Duration::from_secs_f64(self.idle_secs);
+                store
+                    .buckets
+                    .retain(|_, bucket| bucket.updated.elapsed() < idle);
+                let now = Instant::now();
+                store
+                    .logins
+                    .retain(|_, login| now < login.blocked_until + Duration::from_secs(max_secs));
+            }
+            Store::Postgres(pool) => {
+                let conn = pool.get().map_err(|e| e.to_string())?;
+                models::RateLimitBucket::prune(self.idle_secs, &conn)
+                    .and_then(|_| models::LoginFailure::prune(max_secs as f64, &conn))
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// What failed logins to `name` from `req`'s address are counted under.
+    /// Failures from one address don't lock the account for anyone else,
+    /// so guessing a password can't be used to lock its owner out.
+    pub fn login_key(&self, name: &str, req: &HttpRequest) -> String {
+        let address = self.address(&req.connection_info(), req.peer_addr());
+        format!("{}@{}", name, address)
+    }
+
+    /// How long logins under `key` (see `login_key`) are refused for, if they are.
+    pub fn login_blocked_for(&self, key: &str) -> Result<Option<Duration>, String> {
+        match &self.store {
+            Store::Memory(store) => {
+                let store = store.lock().unwrap();
+                let now = Instant::now();
+                Ok(store
+                    .logins
+                    .get(key)
+                    .filter(|login| login.blocked_until > now)
+                    .map(|login| login.blocked_until - now))
+            }
+            Store::Postgres(pool) => {
+                let conn = pool.get().map_err(|e| e.to_string())?;
+                models::LoginFailure::blocked_for(key, &conn)
+                    .map(|wait| wait.map(Duration::from_secs_f64))
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    /// Refuses logins under `key` for a while, doubling each time it's called.
+    pub fn login_failed(&self, key: &str) -> Result<(), String> {
+        let base_secs = self.config.login_backoff_secs;
+        let max_secs = self.config.login_backoff_max_secs;
+        match &self.store {
+            Store::Memory(store) => {
+                let mut store = store.lock().unwrap();
+                let now = Instant::now();
+                let login = store
+                    .logins
+                    .entry(key.to_string())
+                    .or_insert(LoginFailures {
+                        failures: 0,
+                        blocked_until: now,
+                    });
+                // Start over once logins have been allowed again for a while
+                if now > login.blocked_until + Duration::from_secs(max_secs) {
+                    login.failures = 0;
+                }
+                let wait = base_secs
+                    .saturating_mul(1 << login.failures.min(30))
+                    .min(max_secs);
+                login.failures += 1;
+                login.blocked_until = now + Duration::from_secs(wait);
+                Ok(())
+            }
+            Store::Postgres(pool) => {
+                let conn = pool.get().map_err(|e| e.to_string())?;
+                models::LoginFailure::record(key, base_secs as f64, max_secs as f64, &conn)
+                    .map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    pub fn login_succeeded(&self, key: &str) -> Result<(), String> {
+        match &self.store {
+            Store::Memory(store) => {
+                store.lock().unwrap().logins.remove(key);
+                Ok(())
+            }
+            Store::Postgres(pool) => {
+                let conn = pool.get().map_err(|e| e.to_string())?;
+                models::LoginFailure::clear(key, &conn).map_err(|e| e.to_string())
+            }
+        }
+    }
+
+    /// Who a request is counted against: its user if signed in, so people
+    /// sharing an address (such as a school) don't share a limit, or else
+    /// its address.
+    fn client_key(&self, req: &ServiceRequest) -> String {
+        let session_user = req
+            .get_session()
+            .get::<models::SessionUser>("user")
+            .ok()
+            .flatten();
+        if let Some(user) = session_user {
+            return format!("user:{}", user.id);
+        }
+        format!(
+            "ip:{}",
+            self.address(&req.connection_info(), req.peer_addr())
+        )
+    }
+
+    /// The client's address, from `X-Forwarded-For` if it's trusted.
+    fn address(&self, connection_info: &ConnectionInfo, peer_addr: Option<SocketAddr>) -> String {
+        let address = if self.config.trust_forwarded_for {
+            connection_info.realip_remote_addr().map(str::to_string)
+        } else {
+            peer_addr.map(|address| address.ip().to_string())
+        };
+        address.unwrap_or_default()
+    }
+}
+
+/// A `Retry-After` value, which has to be whole seconds.
+pub fn retry_after(wait: Duration) -> String {
+    (wait.as_secs() + u64::from(wait.subsec_nanos() > 0)).to_string()
+}
+
+/// Answers `429 Too Many Requests` once a client has used up its limit.
+/// Has to be wrapped inside the session middleware, to tell who's signed in.
+pub struct RateLimit(pub Arc<RateLimiter>);
+
+impl<S, B> Transform<S> for RateLimit
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RateLimitMiddleware {
+            service: Rc::new(RefCell::new(service)),
+            limiter: self.0.clone(),
+        })
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<RefCell<S>>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S, B> Service for RateLimitMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.borrow_mut().poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let limiter = self.limiter.clone();
+        let limit = limiter
+            .limit_for(req.path())
+            .map(|(prefix, limit)| (prefix.to_string(), limit));
+        // The service isn't borrowed across awaits, so other requests can use it meanwhile
+        let call = move |req: ServiceRequest| service.borrow_mut().call(req);
+        Box::pin(async move {
+            let (prefix, limit) = match limit {
+                Some(limit) => limit,
+                None => return call(req).await,
+            };
+            let key = format!("{} {}", prefix, limiter.client_key(&req));
+            let wait = metrics::block(move || limiter.take(&key, limit)).await;
+            match wait {
+                Ok(None) => call(req).await,
+                Ok(Some(wait)) => {
+                    info!(route = prefix.as_str(), "rate limited");
+                    metrics::RATE_LIMITED.with_label_values(&[prefix.as_str()]).inc();
+                    Ok(req.into_response(
+                        HttpResponse::TooManyRequests()
+                            .header(RETRY_AFTER, retry_after(wait))
+                            .finish()
+                            .into_body(),
+                    ))
+                }
+                // Better to let requests through than to fail them all when the store is down
+                Err(BlockingError::Error(e)) => {
+                    error!("Rate limit store failed: {}", e);
+                    call(req).await
+                }
+                Err(BlockingError::Canceled) => {
+                    Ok(req.into_response(HttpResponse::InternalServerError().finish().into_body()))
+                }
+            }
+        })
+    }
+}